@@ -0,0 +1,58 @@
+use std::io::BufReader;
+use std::process::exit;
+use std::sync::mpsc;
+use std::sync::mpsc::TryRecvError;
+
+use yolol_devices::dap::DapServer;
+use yolol_devices::protocol::read_message;
+use yolol_devices::protocol::write_message;
+
+/// Speaks the Debug Adapter Protocol on stdin and stdout. Requests are read on their own
+/// thread so that the debuggee keeps running until one arrives.
+fn main() {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut stdin = BufReader::new(std::io::stdin());
+        loop {
+            match read_message(&mut stdin) {
+                Ok(Some(message)) => {
+                    if sender.send(message).is_err() {
+                        break;
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    break;
+                }
+            }
+        }
+    });
+
+    let mut server = DapServer::new();
+    let mut stdout = std::io::stdout();
+    while !server.terminated() {
+        let request = if server.running() {
+            match receiver.try_recv() {
+                Ok(request) => Some(request),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => break,
+            }
+        } else {
+            match receiver.recv() {
+                Ok(request) => Some(request),
+                Err(_) => break,
+            }
+        };
+        let messages = match request {
+            Some(request) => server.handle(&request),
+            None => server.run(),
+        };
+        for message in messages {
+            if let Err(e) = write_message(&mut stdout, &message) {
+                eprintln!("{}", e);
+                exit(1)
+            }
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use std::io::BufReader;
+use std::process::exit;
+
+use yolol_devices::lsp::LspServer;
+use yolol_devices::protocol::read_message;
+use yolol_devices::protocol::write_message;
+
+/// Speaks the Language Server Protocol on stdin and stdout.
+fn main() {
+    let mut stdin = BufReader::new(std::io::stdin());
+    let mut stdout = std::io::stdout();
+    let mut server = LspServer::new();
+    loop {
+        let message = match read_message(&mut stdin) {
+            Ok(Some(message)) => message,
+            Ok(None) => exit(1),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1)
+            }
+        };
+        for message in server.handle(&message) {
+            if let Err(e) = write_message(&mut stdout, &message) {
+                eprintln!("{}", e);
+                exit(1)
+            }
+        }
+        if let Some(shutdown) = server.exited() {
+            exit(if shutdown { 0 } else { 1 })
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::io::BufRead;
+use std::io::Write;
+use std::process::exit;
+
+use yolol_devices::repl::Repl;
+use yolol_devices::yolol::YololRunner;
+use yolol_devices::Networks;
+
+fn main() {
+    let path = match std::env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: yolol-repl SHIP");
+            exit(2)
+        }
+    };
+    let (mut networks, violations) =
+        Networks::<YololRunner>::load(&path, false).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            exit(2)
+        });
+    for (path, violation) in violations {
+        eprintln!("{}: {}", path, violation);
+    }
+
+    let mut repl = Repl::new(&networks);
+    println!("type `help` for the commands");
+    let stdin = std::io::stdin();
+    loop {
+        print!("{}> ", repl.network());
+        std::io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+        }
+        if matches!(line.trim(), "quit" | "exit") {
+            break;
+        }
+        match repl.execute(&mut networks, &line) {
+            Ok(out) if out.is_empty() => (),
+            Ok(out) => println!("{}", out),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+}
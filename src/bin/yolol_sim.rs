@@ -0,0 +1,234 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::process::exit;
+
+use yolol_devices::devices::chip::ChipViolation;
+use yolol_devices::trace;
+use yolol_devices::value::YololValue;
+use yolol_devices::vcd::Vcd;
+use yolol_devices::yolol::parse_expr;
+use yolol_devices::yolol::Expr;
+use yolol_devices::yolol::YololRunner;
+use yolol_devices::Network;
+use yolol_devices::Networks;
+use yolol_devices::TickModel;
+
+const USAGE: &str = "usage: yolol-sim SHIP [options]
+
+Runs a ship file. Chip scripts are looked up relative to the working directory.
+
+options:
+  -n, --ticks N          run N ticks, 1 by default or 10000 with --until
+  -u, --until NET:EXPR   stop once the YOLOL expression EXPR holds on network NET,
+                         e.g. `main::Door == 1`
+  -p, --print NET:FIELD  print a field after the run, every global if none is given
+  -e, --every            print the fields after every tick
+      --vcd FILE         dump the printed fields to FILE as a VCD waveform
+      --trace FILE       write every field change to FILE as JSON lines
+      --strict           refuse to load scripts over the chip limits
+      --interleaved      run the chips one after another, as in game
+
+exit status: 0 on success, 1 if a chip hit a runtime error, 2 on bad usage or ship,
+including scripts that cannot be read or parsed and, with --strict, scripts over the chip
+limits, 3 if the --until condition never held";
+
+#[derive(Default)]
+struct Options {
+    ship: String,
+    ticks: Option<u64>,
+    until: Option<(String, Expr)>,
+    print: Vec<(String, String)>,
+    every: bool,
+    vcd: Option<String>,
+    trace: Option<String>,
+    strict: bool,
+    interleaved: bool,
+}
+
+fn main() {
+    let options = parse_args(std::env::args().skip(1).collect()).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, USAGE);
+        exit(2)
+    });
+    exit(run(options).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        2
+    }))
+}
+
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                exit(0)
+            }
+            "-n" | "--ticks" => {
+                let ticks = value(&arg)?;
+                options.ticks = Some(
+                    ticks
+                        .parse()
+                        .map_err(|_| format!("bad tick count {}", ticks))?,
+                );
+            }
+            "-u" | "--until" => {
+                let (network, expr) = split_network(&value(&arg)?)?;
+                let expr = parse_expr(&expr).map_err(|e| format!("--until: {}", e))?;
+                options.until = Some((network, expr));
+            }
+            "-p" | "--print" => {
+                let (network, field) = split_network(&value(&arg)?)?;
+                options
+                    .print
+                    .push((network, field.trim_start_matches(':').to_lowercase()));
+            }
+            "-e" | "--every" => options.every = true,
+            "--vcd" => options.vcd = Some(value(&arg)?),
+            "--trace" => options.trace = Some(value(&arg)?),
+            "--strict" => options.strict = true,
+            "--interleaved" => options.interleaved = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if options.ship.is_empty() => options.ship = arg,
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    if options.ship.is_empty() {
+        return Err("missing ship file".to_string());
+    }
+    Ok(options)
+}
+
+/// Split `main::Door` into the network `main` and `:Door`.
+fn split_network(arg: &str) -> Result<(String, String), String> {
+    match arg.find(':') {
+        Some(i) if i > 0 => Ok((arg[..i].to_string(), arg[i + 1..].to_string())),
+        _ => Err(format!("expected NETWORK:..., got {}", arg)),
+    }
+}
+
+fn run(options: Options) -> Result<i32, String> {
+    let (mut networks, violations) = Networks::<YololRunner>::load(&options.ship, options.strict)?;
+    if options.interleaved {
+        networks.set_tick_model(TickModel::Interleaved);
+    }
+    let mut refused = false;
+    for (path, violation) in violations {
+        eprintln!("{}: {}", path, violation);
+        refused |= options.strict
+            || matches!(
+                violation,
+                ChipViolation::Unreadable(_) | ChipViolation::Syntax { .. }
+            );
+    }
+    if refused {
+        return Ok(2);
+    }
+
+    let print = if options.print.is_empty() {
+        networks
+            .networks()
+            .flat_map(|(name, network)| {
+                network
+                    .globals()
+                    .into_iter()
+                    .map(move |field| (name.clone(), field.name().to_string()))
+            })
+            .collect()
+    } else {
+        options.print.clone()
+    };
+    for (network, field) in &print {
+        if networks
+            .network(network)
+            .and_then(|n| n.get_field(field))
+            .is_none()
+        {
+            eprintln!("warning: no field {}::{}", network, field);
+        }
+    }
+    if let Some((network, _)) = &options.until {
+        networks
+            .network(network)
+            .ok_or(format!("--until: no network {}", network))?;
+    }
+
+    let mut vcd = match &options.vcd {
+        Some(path) => {
+            let file = File::create(path).map_err(|e| format!("cannot create {}: {}", path, e))?;
+            Some(Vcd::new(BufWriter::new(file), print.clone()))
+        }
+        None => None,
+    };
+    let mut changes = vec![];
+    networks.set_tracing(options.trace.is_some());
+
+    let ticks = options
+        .ticks
+        .unwrap_or(if options.until.is_some() { 10000 } else { 1 });
+    let mut status = 0;
+    let mut reached = false;
+    for _ in 0..ticks {
+        if let Some(vcd) = &mut vcd {
+            vcd.sample(&networks).map_err(|e| e.to_string())?;
+        }
+        let tick = networks.tick();
+        for (name, outcomes) in networks.step() {
+            for chip in outcomes {
+                if let Some(error) = chip.outcome.error {
+                    eprintln!(
+                        "tick {}: {} device {} slot {} line {}: {}",
+                        tick, name, chip.device, chip.slot, chip.outcome.line, error
+                    );
+                    status = 1;
+                }
+            }
+        }
+        changes.append(&mut networks.take_trace());
+        if options.every {
+            println!("tick {}", networks.tick());
+            print_fields(&networks, &print);
+        }
+        if let Some((network, expr)) = &options.until {
+            if holds(networks.network(network).unwrap(), expr) {
+                reached = true;
+                break;
+            }
+        }
+    }
+    if let Some(vcd) = &mut vcd {
+        vcd.sample(&networks).map_err(|e| e.to_string())?;
+    }
+
+    if let Some(path) = &options.trace {
+        let file = File::create(path).map_err(|e| format!("cannot create {}: {}", path, e))?;
+        trace::write_jsonl(&changes, BufWriter::new(file)).map_err(|e| e.to_string())?;
+    }
+    if !options.every {
+        print_fields(&networks, &print);
+    }
+    if options.until.is_some() && !reached {
+        eprintln!("condition not met after {} ticks", ticks);
+        if status == 0 {
+            status = 3;
+        }
+    }
+    Ok(status)
+}
+
+fn holds(network: &Network<YololRunner>, expr: &Expr) -> bool {
+    match network.environment().eval(expr) {
+        Ok(YololValue::Int(v)) => (&v).into(),
+        _ => false,
+    }
+}
+
+fn print_fields(networks: &Networks<YololRunner>, print: &[(String, String)]) {
+    for (network, field) in print {
+        if let Some(value) = networks.network(network).and_then(|n| n.get_field(field)) {
+            println!("{}::{} = {}", network, field, value.to_literal());
+        }
+    }
+}
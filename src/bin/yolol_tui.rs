@@ -0,0 +1,345 @@
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::process::exit;
+use std::time::Duration;
+use std::time::Instant;
+
+use crossterm::event;
+use crossterm::event::Event;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEventKind;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Block;
+use ratatui::widgets::Borders;
+use ratatui::widgets::List;
+use ratatui::widgets::ListItem;
+use ratatui::widgets::ListState;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Tabs;
+use ratatui::Frame;
+use yolol_devices::devices::chip::Chip;
+use yolol_devices::devices::Device;
+use yolol_devices::devices::DeviceTrait;
+use yolol_devices::trace::ChangeSource;
+use yolol_devices::value::YololValue;
+use yolol_devices::yolol::YololRunner;
+use yolol_devices::Network;
+use yolol_devices::Networks;
+
+/// A tick lasts 200 ms in game.
+const TICK: Duration = Duration::from_millis(200);
+const LOG_SIZE: usize = 500;
+
+const HELP: &str = "q quit  space pause  s step  tab network  up/down select  enter toggle";
+
+struct App {
+    networks: Networks<YololRunner>,
+    names: Vec<String>,
+    sources: BTreeMap<String, Vec<String>>,
+    network: usize,
+    field: usize,
+    paused: bool,
+    log: VecDeque<String>,
+}
+
+impl App {
+    fn new(mut networks: Networks<YololRunner>) -> Self {
+        networks.set_tracing(true);
+        let names = networks.networks().map(|(name, _)| name.clone()).collect();
+        let mut sources = BTreeMap::new();
+        for (_, network) in networks.networks() {
+            for device in network.devices() {
+                if let Device::Rack(rack) = device {
+                    for chip in rack.chips() {
+                        if let Chip::Yolol(chip) = chip {
+                            if let Some(path) = chip.path() {
+                                let source = std::fs::read_to_string(path).unwrap_or_default();
+                                sources.insert(
+                                    path.to_string(),
+                                    source.lines().map(|l| l.to_string()).collect(),
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Self {
+            networks,
+            names,
+            sources,
+            network: 0,
+            field: 0,
+            paused: false,
+            log: VecDeque::new(),
+        }
+    }
+
+    fn current(&self) -> Option<&Network<YololRunner>> {
+        self.networks.network(self.names.get(self.network)?)
+    }
+
+    /// The fields of the current network, device by device.
+    fn fields(&self) -> Vec<(usize, String, YololValue)> {
+        let mut fields = vec![];
+        if let Some(network) = self.current() {
+            for (i, device) in network.devices().iter().enumerate() {
+                for field in device.get_fields() {
+                    fields.push((i, field.name().to_string(), (**field).clone()));
+                }
+            }
+        }
+        fields
+    }
+
+    fn step(&mut self) {
+        let tick = self.networks.tick();
+        for (name, outcomes) in self.networks.step() {
+            for chip in outcomes {
+                if let Some(error) = chip.outcome.error {
+                    self.push_log(format!(
+                        "{} {} device {} slot {} line {}: {}",
+                        tick, name, chip.device, chip.slot, chip.outcome.line, error
+                    ));
+                }
+            }
+        }
+        for change in self.networks.take_trace() {
+            let source = match change.source {
+                ChangeSource::Chip { device, slot, line } => {
+                    format!("device {} slot {} line {}", device, slot, line)
+                }
+                ChangeSource::Device { device } => format!("device {}", device),
+                ChangeSource::Relay { network } => format!("relay from {}", network),
+                ChangeSource::External => "you".to_string(),
+            };
+            self.push_log(format!(
+                "{} {} :{} {} -> {} ({})",
+                change.tick,
+                change.network,
+                change.field,
+                change.old.to_literal(),
+                change.new.to_literal(),
+                source
+            ));
+        }
+    }
+
+    fn push_log(&mut self, line: String) {
+        if self.log.len() == LOG_SIZE {
+            self.log.pop_front();
+        }
+        self.log.push_back(line);
+    }
+
+    /// Press the selected field like a button, flipping it between 0 and 1.
+    fn toggle(&mut self) {
+        let fields = self.fields();
+        let (name, value) = match fields.get(self.field) {
+            Some((_, name, YololValue::Int(v))) => (name.clone(), !bool::from(v)),
+            _ => return,
+        };
+        let network = self.names[self.network].clone();
+        self.networks.set_field(&network, &name, value.into());
+        for change in self.networks.take_trace() {
+            self.push_log(format!(
+                "{} {} :{} {} -> {} (you)",
+                change.tick,
+                change.network,
+                change.field,
+                change.old.to_literal(),
+                change.new.to_literal()
+            ));
+        }
+    }
+}
+
+fn main() {
+    let path = match std::env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: yolol-tui SHIP");
+            exit(2)
+        }
+    };
+    let (networks, violations) = Networks::<YololRunner>::load(&path, false).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        exit(2)
+    });
+    for (path, violation) in violations {
+        eprintln!("{}: {}", path, violation);
+    }
+
+    let mut app = App::new(networks);
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut app);
+    ratatui::restore();
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        exit(1)
+    }
+}
+
+fn run(terminal: &mut ratatui::DefaultTerminal, app: &mut App) -> std::io::Result<()> {
+    let mut last = Instant::now();
+    loop {
+        terminal.draw(|frame| draw(frame, app))?;
+        let timeout = TICK.saturating_sub(last.elapsed());
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                let count = app.fields().len();
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char(' ') => app.paused = !app.paused,
+                    KeyCode::Char('s') => app.step(),
+                    KeyCode::Tab if !app.names.is_empty() => {
+                        app.network = (app.network + 1) % app.names.len();
+                        app.field = 0;
+                    }
+                    KeyCode::Up => app.field = app.field.saturating_sub(1),
+                    KeyCode::Down if app.field + 1 < count => app.field += 1,
+                    KeyCode::Enter => app.toggle(),
+                    _ => (),
+                }
+            }
+        }
+        if last.elapsed() >= TICK {
+            if !app.paused {
+                app.step();
+            }
+            last = Instant::now();
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, app: &App) {
+    let [tabs, main, log, help] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(5),
+        Constraint::Length(10),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [fields, chips] =
+        Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)]).areas(main);
+
+    let state = if app.paused { "paused" } else { "running" };
+    let title = format!(" tick {} ({}) ", app.networks.tick(), state);
+    frame.render_widget(
+        Tabs::new(app.names.clone())
+            .select(app.network)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .block(Block::default().borders(Borders::ALL).title(title)),
+        tabs,
+    );
+
+    draw_fields(frame, app, fields);
+    draw_chips(frame, app, chips);
+
+    let height = log.height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = app
+        .log
+        .iter()
+        .skip(app.log.len().saturating_sub(height))
+        .map(|l| Line::from(l.as_str()))
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" changes ")),
+        log,
+    );
+    frame.render_widget(Paragraph::new(HELP), help);
+}
+
+fn draw_fields(frame: &mut Frame, app: &App, area: Rect) {
+    let network = match app.current() {
+        Some(network) => network,
+        None => return,
+    };
+    let mut items = vec![];
+    let mut selected = None;
+    let mut index = 0;
+    for (i, device) in network.devices().iter().enumerate() {
+        items.push(ListItem::new(Line::from(Span::styled(
+            format!("{} {}", i, device.get_device_name()),
+            Style::default().add_modifier(Modifier::BOLD),
+        ))));
+        for field in device.get_fields() {
+            if index == app.field {
+                selected = Some(items.len());
+            }
+            index += 1;
+            items.push(ListItem::new(format!(
+                "  :{} = {}",
+                field.name(),
+                field.to_literal()
+            )));
+        }
+    }
+    let mut state = ListState::default().with_selected(selected);
+    frame.render_stateful_widget(
+        List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .block(Block::default().borders(Borders::ALL).title(" devices ")),
+        area,
+        &mut state,
+    );
+}
+
+fn draw_chips(frame: &mut Frame, app: &App, area: Rect) {
+    let network = match app.current() {
+        Some(network) => network,
+        None => return,
+    };
+    let mut chips = vec![];
+    for (i, device) in network.devices().iter().enumerate() {
+        if let Device::Rack(rack) = device {
+            for (slot, chip) in rack.chips().enumerate() {
+                if let Chip::Yolol(chip) = chip {
+                    chips.push((i, slot, rack.powered(), chip));
+                }
+            }
+        }
+    }
+    if chips.is_empty() {
+        return;
+    }
+    let areas = Layout::vertical(vec![Constraint::Fill(1); chips.len()]).split(area);
+    for ((device, slot, powered, chip), area) in chips.into_iter().zip(areas.iter()) {
+        let path = chip.path().unwrap_or("no script");
+        let current = chip.runner().map(|runner| runner.line());
+        let source = app.sources.get(path).cloned().unwrap_or_default();
+        let height = area.height.saturating_sub(2) as usize;
+        let skip = current
+            .map(|line| line.saturating_sub(height / 2 + 1))
+            .unwrap_or(0);
+        let lines: Vec<Line> = source
+            .iter()
+            .enumerate()
+            .skip(skip)
+            .map(|(n, text)| {
+                let line = Line::from(format!("{:>2} {}", n + 1, text));
+                if Some(n + 1) == current {
+                    line.style(Style::default().bg(Color::Blue).fg(Color::White))
+                } else {
+                    line
+                }
+            })
+            .collect();
+        let power = if powered { "" } else { " (off)" };
+        let title = format!(" device {} slot {}: {}{} ", device, slot, path, power);
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)),
+            *area,
+        );
+    }
+}
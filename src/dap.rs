@@ -0,0 +1,553 @@
+//! A Debug Adapter Protocol server driving a [`Debugger`]. Each chip running a script is a
+//! thread of the debuggee, with a single stack frame at the line it runs next.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde_json::json;
+use serde_json::Value;
+
+use crate::debugger::Breakpoint;
+use crate::debugger::Debugger;
+use crate::debugger::Stop;
+use crate::devices::chip::Chip;
+use crate::devices::Device;
+use crate::protocol::same_file;
+use crate::value::YololValue;
+use crate::yolol::parse_expr;
+use crate::yolol::Environment;
+use crate::yolol::YololRunner;
+use crate::ChipId;
+use crate::Networks;
+
+/// Ticks run between two looks at the incoming requests while the debuggee runs.
+const TICKS_PER_SLICE: u64 = 50;
+
+#[derive(Default)]
+pub struct DapServer {
+    seq: u64,
+    debugger: Option<Debugger<YololRunner>>,
+    /// The threads, thread `i + 1` being `chips[i]`.
+    chips: Vec<(ChipId, String)>,
+    running: bool,
+    stop_on_entry: bool,
+    terminated: bool,
+}
+
+impl DapServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the debuggee runs, in which case `run` should be called between requests.
+    pub fn running(&self) -> bool {
+        self.running
+    }
+
+    /// Whether the client asked to end the session.
+    pub fn terminated(&self) -> bool {
+        self.terminated
+    }
+
+    /// Handle a request, returning the response and the events it caused.
+    pub fn handle(&mut self, request: &Value) -> Vec<Value> {
+        let command = request["command"].as_str().unwrap_or_default().to_string();
+        let args = &request["arguments"];
+        // The response comes before the events it causes.
+        self.seq += 1;
+        let seq = self.seq;
+        let mut events = vec![];
+        let result = match command.as_str() {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsConditionalBreakpoints": true,
+                "supportsDataBreakpoints": true,
+                "supportsEvaluateForHovers": true,
+                "supportsTerminateRequest": true,
+                "exceptionBreakpointFilters": [
+                    {"filter": "runtime", "label": "Runtime errors", "default": false}
+                ],
+            })),
+            "launch" => self
+                .launch(args, &mut events)
+                .inspect(|_| events.push(self.event("initialized", json!({})))),
+            "setBreakpoints" => self.set_breakpoints(args),
+            "setExceptionBreakpoints" => self.debugger().map(|debugger| {
+                let filters = args["filters"].as_array().cloned().unwrap_or_default();
+                debugger.set_break_on_error(filters.iter().any(|f| f == "runtime"));
+                json!({})
+            }),
+            "dataBreakpointInfo" => Ok(self.data_breakpoint_info(args)),
+            "setDataBreakpoints" => self.set_data_breakpoints(args),
+            "configurationDone" => {
+                if self.stop_on_entry {
+                    events.push(self.stopped("entry", 1, None));
+                } else {
+                    self.running = true;
+                }
+                Ok(json!({}))
+            }
+            "threads" => Ok(json!({
+                "threads": self
+                    .chips
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (chip, path))| json!({
+                        "id": i + 1,
+                        "name": format!(
+                            "{} device {} slot {}: {}",
+                            chip.network, chip.device, chip.slot, path
+                        ),
+                    }))
+                    .collect::<Vec<_>>(),
+            })),
+            "stackTrace" => self.stack_trace(args),
+            "scopes" => self.scopes(args),
+            "variables" => self.variables(args),
+            "evaluate" => self.evaluate(args),
+            "continue" => {
+                self.running = true;
+                Ok(json!({"allThreadsContinued": true}))
+            }
+            "next" | "stepIn" | "stepOut" => self.step_line(args, &mut events),
+            "pause" => {
+                self.running = false;
+                let thread = args["threadId"].as_u64().unwrap_or(1) as usize;
+                events.push(self.stopped("pause", thread, None));
+                Ok(json!({}))
+            }
+            "disconnect" | "terminate" => {
+                self.running = false;
+                self.terminated = true;
+                events.push(self.event("terminated", json!({})));
+                Ok(json!({}))
+            }
+            _ => Err(format!("unsupported request {}", command)),
+        };
+        let mut response = json!({
+            "seq": seq,
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": command,
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+        let mut messages = vec![response];
+        messages.append(&mut events);
+        messages
+    }
+
+    /// Run the debuggee for a while, returning the events sent when it stops.
+    pub fn run(&mut self) -> Vec<Value> {
+        let stop = match &mut self.debugger {
+            Some(debugger) if self.running => debugger.resume(TICKS_PER_SLICE),
+            _ => return vec![],
+        };
+        match stop {
+            Stop::Done => vec![],
+            Stop::Breakpoint { chip, .. } => {
+                self.running = false;
+                let thread = self.thread(&chip);
+                vec![self.stopped("breakpoint", thread, None)]
+            }
+            Stop::Watchpoint {
+                network,
+                field,
+                old,
+                new,
+            } => {
+                self.running = false;
+                let thread = self.next_thread();
+                let text = format!(":{} on {}: {} -> {}", field, network, old, new);
+                vec![self.stopped("data breakpoint", thread, Some(text))]
+            }
+            Stop::Error { chip, line, error } => {
+                self.running = false;
+                let thread = self.thread(&chip);
+                let text = format!("line {}: {}", line, error);
+                vec![self.stopped("exception", thread, Some(text))]
+            }
+        }
+    }
+
+    fn debugger(&mut self) -> Result<&mut Debugger<YololRunner>, String> {
+        self.debugger
+            .as_mut()
+            .ok_or_else(|| "no ship launched".to_string())
+    }
+
+    fn event(&mut self, event: &str, body: Value) -> Value {
+        self.seq += 1;
+        json!({"seq": self.seq, "type": "event", "event": event, "body": body})
+    }
+
+    fn stopped(&mut self, reason: &str, thread: usize, text: Option<String>) -> Value {
+        let mut body = json!({
+            "reason": reason,
+            "threadId": thread,
+            "allThreadsStopped": true,
+        });
+        if let Some(text) = text {
+            body["text"] = json!(text);
+        }
+        self.event("stopped", body)
+    }
+
+    fn thread(&self, chip: &ChipId) -> usize {
+        self.chips.iter().position(|(c, _)| c == chip).unwrap_or(0) + 1
+    }
+
+    /// The thread of the chip to run next, or the first one between ticks.
+    fn next_thread(&self) -> usize {
+        match self.debugger.as_ref().and_then(|d| d.next_chip()) {
+            Some(chip) => self.thread(chip),
+            None => 1,
+        }
+    }
+
+    fn chip(&self, args: &Value, key: &str) -> Result<&ChipId, String> {
+        let thread = args[key].as_u64().unwrap_or(0) as usize;
+        self.chips
+            .get(thread.wrapping_sub(1))
+            .map(|(chip, _)| chip)
+            .ok_or_else(|| format!("no thread {}", thread))
+    }
+
+    /// Load the ship, reporting what is wrong with its scripts on the debug console. The ship
+    /// file and the scripts are looked up from `cwd` when it is given.
+    fn launch(&mut self, args: &Value, events: &mut Vec<Value>) -> Result<Value, String> {
+        let cwd = Path::new(args["cwd"].as_str().unwrap_or_default());
+        let program = args["program"]
+            .as_str()
+            .ok_or("launch needs a program, the ship file")?;
+        let (mut networks, violations) = Networks::<YololRunner>::load_in(cwd, program, false)?;
+        for (path, violation) in violations {
+            let output = format!("{}: {}\n", path, violation);
+            events.push(self.event("output", json!({"category": "console", "output": output})));
+        }
+        if args["interleaved"].as_bool().unwrap_or(false) {
+            networks.set_tick_model(crate::TickModel::Interleaved);
+        }
+        self.chips = networks
+            .chips()
+            .into_iter()
+            .filter_map(|chip| {
+                let network = networks.network(&chip.network)?;
+                match network.devices().get(chip.device)? {
+                    Device::Rack(rack) => match rack.chip(chip.slot)? {
+                        Chip::Yolol(yolol) => {
+                            let path = yolol.path()?.to_string();
+                            Some((chip, path))
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            })
+            .collect();
+        self.stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or(false);
+        self.debugger = Some(Debugger::new(networks));
+        Ok(json!({}))
+    }
+
+    fn set_breakpoints(&mut self, args: &Value) -> Result<Value, String> {
+        let source = args["source"]["path"]
+            .as_str()
+            .ok_or("missing source path")?;
+        let paths: Vec<String> = self
+            .chips
+            .iter()
+            .map(|(_, path)| path.clone())
+            .filter(|path| same_file(path, source))
+            .collect();
+        let debugger = self.debugger()?;
+        for path in &paths {
+            debugger.clear_breakpoints(path);
+        }
+        let mut result = vec![];
+        for breakpoint in args["breakpoints"].as_array().cloned().unwrap_or_default() {
+            let line = breakpoint["line"].as_u64().unwrap_or(0) as usize;
+            let condition = match breakpoint["condition"].as_str() {
+                Some(condition) if !condition.trim().is_empty() => match parse_expr(condition) {
+                    Ok(expr) => Some(expr),
+                    Err(e) => {
+                        result.push(
+                            json!({"verified": false, "line": line, "message": e.to_string()}),
+                        );
+                        continue;
+                    }
+                },
+                _ => None,
+            };
+            for path in &paths {
+                debugger.add_breakpoint(Breakpoint {
+                    path: path.clone(),
+                    line,
+                    condition: condition.clone(),
+                });
+            }
+            result.push(json!({"verified": !paths.is_empty(), "line": line}));
+        }
+        Ok(json!({ "breakpoints": result }))
+    }
+
+    /// Globals can be watched, by `network::field`.
+    fn data_breakpoint_info(&self, args: &Value) -> Value {
+        let reference = args["variablesReference"].as_u64().unwrap_or(0) as usize;
+        let name = args["name"].as_str().unwrap_or_default();
+        match self.chips.get((reference / 2).wrapping_sub(1)) {
+            Some((chip, _)) if reference % 2 == 1 => json!({
+                "dataId": format!("{}::{}", chip.network, name.trim_start_matches(':')),
+                "description": format!("{} on {}", name, chip.network),
+                "accessTypes": ["write"],
+            }),
+            _ => json!({"dataId": null, "description": "only globals can be watched"}),
+        }
+    }
+
+    fn set_data_breakpoints(&mut self, args: &Value) -> Result<Value, String> {
+        let mut networks = vec![];
+        for (chip, _) in &self.chips {
+            if !networks.contains(&chip.network) {
+                networks.push(chip.network.clone());
+            }
+        }
+        let debugger = self.debugger()?;
+        for network in &networks {
+            let fields: Vec<String> = debugger
+                .networks()
+                .network(network)
+                .map(|n| n.globals().iter().map(|f| f.name().to_string()).collect())
+                .unwrap_or_default();
+            for field in fields {
+                debugger.remove_watchpoint(network, &field);
+            }
+        }
+        let mut result = vec![];
+        for breakpoint in args["breakpoints"].as_array().cloned().unwrap_or_default() {
+            let id = breakpoint["dataId"].as_str().unwrap_or_default();
+            match id.split_once("::") {
+                Some((network, field)) => {
+                    debugger.add_watchpoint(network, field);
+                    result.push(json!({"verified": true}));
+                }
+                None => result.push(json!({"verified": false})),
+            }
+        }
+        Ok(json!({ "breakpoints": result }))
+    }
+
+    fn stack_trace(&mut self, args: &Value) -> Result<Value, String> {
+        let chip = self.chip(args, "threadId")?.clone();
+        let thread = self.thread(&chip);
+        let path = self.chips[thread - 1].1.clone();
+        let line = self.debugger()?.state(&chip).map_or(0, |state| state.line);
+        Ok(json!({
+            "stackFrames": [{
+                "id": thread,
+                "name": format!("{} device {} slot {}", chip.network, chip.device, chip.slot),
+                "source": {"name": file_name(&path), "path": absolute(&path)},
+                "line": line,
+                "column": 1,
+            }],
+            "totalFrames": 1,
+        }))
+    }
+
+    fn scopes(&mut self, args: &Value) -> Result<Value, String> {
+        let frame = args["frameId"].as_u64().unwrap_or(0) as usize;
+        self.chip(args, "frameId")?;
+        Ok(json!({
+            "scopes": [
+                {"name": "Locals", "variablesReference": frame * 2, "expensive": false},
+                {"name": "Globals", "variablesReference": frame * 2 + 1, "expensive": false},
+            ]
+        }))
+    }
+
+    fn variables(&mut self, args: &Value) -> Result<Value, String> {
+        let reference = args["variablesReference"].as_u64().unwrap_or(0) as usize;
+        let (chip, _) = self
+            .chips
+            .get((reference / 2).wrapping_sub(1))
+            .cloned()
+            .ok_or("unknown variables")?;
+        let env = self.env(&chip)?;
+        let (prefix, variables) = if reference % 2 == 0 {
+            ("", env.locals)
+        } else {
+            (":", env.globals)
+        };
+        Ok(json!({
+            "variables": variables
+                .iter()
+                .map(|(name, value)| json!({
+                    "name": format!("{}{}", prefix, name),
+                    "value": value.to_literal(),
+                    "type": value_type(value),
+                    "variablesReference": 0,
+                }))
+                .collect::<Vec<_>>(),
+        }))
+    }
+
+    fn evaluate(&mut self, args: &Value) -> Result<Value, String> {
+        let expr = parse_expr(args["expression"].as_str().unwrap_or_default())
+            .map_err(|e| e.to_string())?;
+        let chip = match self.chip(args, "frameId") {
+            Ok(chip) => chip.clone(),
+            Err(_) => self.chips.first().ok_or("no chip")?.0.clone(),
+        };
+        let value = self.env(&chip)?.eval(&expr).map_err(|e| e.to_string())?;
+        Ok(json!({
+            "result": value.to_literal(),
+            "type": value_type(&value),
+            "variablesReference": 0,
+        }))
+    }
+
+    /// Run one line of the thread's chip while the others stay frozen.
+    fn step_line(&mut self, args: &Value, events: &mut Vec<Value>) -> Result<Value, String> {
+        let chip = self.chip(args, "threadId")?.clone();
+        let thread = self.thread(&chip);
+        self.running = false;
+        let stop = self.debugger()?.step_line(&chip);
+        let event = match stop {
+            Some(Stop::Error { line, error, .. }) => self.stopped(
+                "exception",
+                thread,
+                Some(format!("line {}: {}", line, error)),
+            ),
+            Some(Stop::Watchpoint { field, new, .. }) => self.stopped(
+                "data breakpoint",
+                thread,
+                Some(format!(":{} = {}", field, new)),
+            ),
+            _ => self.stopped("step", thread, None),
+        };
+        events.push(event);
+        Ok(json!({}))
+    }
+
+    /// The chip's locals and its network's globals.
+    fn env(&mut self, chip: &ChipId) -> Result<Environment, String> {
+        let debugger = self.debugger()?;
+        let mut env = debugger
+            .networks()
+            .network(&chip.network)
+            .map(|network| network.environment())
+            .unwrap_or_default();
+        if let Some(state) = debugger.state(chip) {
+            env.locals = state.locals;
+        }
+        Ok(env)
+    }
+}
+
+fn absolute(path: &str) -> String {
+    Path::new(path)
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(path))
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map_or(path.into(), |name| name.to_string_lossy().into_owned())
+}
+
+fn value_type(value: &YololValue) -> &'static str {
+    match value {
+        YololValue::Int(_) => "number",
+        YololValue::String(_) => "string",
+    }
+}
+
+#[test]
+fn session_test() {
+    let dir = crate::TestDir::new();
+    let script = dir.write("light.yolol", ":light = 1\nx++\ngoto 2");
+    dir.write(
+        "ship.yaml",
+        "networks:
+  - name: main
+    devices:
+      - !lamp
+        LampOn: Light
+      - !rack
+        module: !chip_reader
+          slot1: !yolol_chip
+            script: light.yolol
+",
+    );
+
+    let mut server = DapServer::new();
+    let mut seq = 0;
+    let mut request = |server: &mut DapServer, command: &str, arguments: Value| {
+        seq += 1;
+        let messages = server.handle(
+            &json!({"seq": seq, "type": "request", "command": command, "arguments": arguments}),
+        );
+        assert_eq!(messages[0]["success"], json!(true), "{}", messages[0]);
+        messages
+    };
+    request(&mut server, "initialize", json!({}));
+    let messages = request(
+        &mut server,
+        "launch",
+        json!({"program": "ship.yaml", "cwd": dir.path().to_string_lossy(), "stopOnEntry": true}),
+    );
+    assert_eq!(messages[1]["event"], json!("initialized"));
+    let messages = request(
+        &mut server,
+        "setBreakpoints",
+        json!({"source": {"path": script}, "breakpoints": [{"line": 3}]}),
+    );
+    assert_eq!(
+        messages[0]["body"]["breakpoints"][0]["verified"],
+        json!(true)
+    );
+    let messages = request(&mut server, "configurationDone", json!({}));
+    assert_eq!(messages[1]["body"]["reason"], json!("entry"));
+    let messages = request(&mut server, "threads", json!({}));
+    assert_eq!(messages[0]["body"]["threads"].as_array().unwrap().len(), 1);
+
+    request(&mut server, "continue", json!({"threadId": 1}));
+    let messages = server.run();
+    assert_eq!(messages[0]["body"]["reason"], json!("breakpoint"));
+    assert!(!server.running());
+    let messages = request(&mut server, "stackTrace", json!({"threadId": 1}));
+    assert_eq!(messages[0]["body"]["stackFrames"][0]["line"], json!(3));
+    let messages = request(&mut server, "variables", json!({"variablesReference": 2}));
+    assert_eq!(
+        messages[0]["body"]["variables"][0],
+        json!({"name": "x", "value": "1", "type": "number", "variablesReference": 0})
+    );
+    let messages = request(&mut server, "variables", json!({"variablesReference": 3}));
+    assert!(messages[0]["body"]["variables"]
+        .as_array()
+        .unwrap()
+        .contains(
+            &json!({"name": ":light", "value": "1", "type": "number", "variablesReference": 0})
+        ));
+
+    let messages = request(&mut server, "next", json!({"threadId": 1}));
+    assert_eq!(messages[1]["body"]["reason"], json!("step"));
+    let messages = request(&mut server, "stackTrace", json!({"threadId": 1}));
+    assert_eq!(messages[0]["body"]["stackFrames"][0]["line"], json!(2));
+    let messages = request(
+        &mut server,
+        "evaluate",
+        json!({"expression": "x + 1", "frameId": 1}),
+    );
+    assert_eq!(messages[0]["body"]["result"], json!("2"));
+
+    let messages = request(&mut server, "disconnect", json!({}));
+    assert_eq!(messages[1]["event"], json!("terminated"));
+    assert!(server.terminated());
+}
@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+
+use crate::devices::chip::Chip;
+use crate::devices::chip::CodeRunner;
+use crate::devices::chip::RunnerState;
+use crate::devices::chip::RuntimeError;
+use crate::devices::chip::YololChip;
+use crate::devices::Device;
+use crate::value::YololValue;
+use crate::yolol::Expr;
+use crate::ChipId;
+use crate::Networks;
+
+/// Stops a chip before it runs `line` of the script at `path`, if `condition` holds for
+/// the chip's locals and its network's globals. Lines are 1-based.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    pub path: String,
+    pub line: usize,
+    pub condition: Option<Expr>,
+}
+
+/// Why the debugger stopped.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stop {
+    /// `chip` is about to run `line`.
+    Breakpoint { chip: ChipId, line: usize },
+    /// A watched field changed.
+    Watchpoint {
+        network: String,
+        field: String,
+        old: YololValue,
+        new: YololValue,
+    },
+    /// `chip` hit a runtime error on `line`.
+    Error {
+        chip: ChipId,
+        line: usize,
+        error: RuntimeError,
+    },
+    /// The requested ticks were run.
+    Done,
+}
+
+/// Runs a simulation chip by chip, so that it can stop in the middle of a tick.
+#[derive(Debug)]
+pub struct Debugger<R: CodeRunner + Default> {
+    networks: Networks<R>,
+    breakpoints: Vec<Breakpoint>,
+    watchpoints: BTreeMap<(String, String), Option<YololValue>>,
+    break_on_error: bool,
+    /// The chips left to run in the current tick, empty between ticks.
+    pending: VecDeque<ChipId>,
+    /// The chip stopped at, which resumes without hitting its breakpoint again.
+    resume: Option<ChipId>,
+}
+
+impl<R: CodeRunner + Default> Debugger<R> {
+    pub fn new(networks: Networks<R>) -> Self {
+        Self {
+            networks,
+            breakpoints: vec![],
+            watchpoints: BTreeMap::new(),
+            break_on_error: false,
+            pending: VecDeque::new(),
+            resume: None,
+        }
+    }
+
+    pub fn networks(&self) -> &Networks<R> {
+        &self.networks
+    }
+
+    pub fn networks_mut(&mut self) -> &mut Networks<R> {
+        &mut self.networks
+    }
+
+    pub fn into_networks(self) -> Networks<R> {
+        self.networks
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
+    /// Remove the breakpoints of a script.
+    pub fn clear_breakpoints(&mut self, path: &str) {
+        self.breakpoints.retain(|b| b.path != path);
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Stop when the field `field` of the network `network` changes. The leading `:` is
+    /// optional.
+    pub fn add_watchpoint(&mut self, network: &str, field: &str) {
+        let field = field.trim_start_matches(':').to_lowercase();
+        let value = self.field(network, &field);
+        self.watchpoints.insert((network.to_string(), field), value);
+    }
+
+    pub fn remove_watchpoint(&mut self, network: &str, field: &str) {
+        let field = field.trim_start_matches(':').to_lowercase();
+        self.watchpoints.remove(&(network.to_string(), field));
+    }
+
+    /// Stop on chip runtime errors.
+    pub fn set_break_on_error(&mut self, break_on_error: bool) {
+        self.break_on_error = break_on_error;
+    }
+
+    /// Get the chip the debugger stopped at, the next one to run in the current tick.
+    pub fn next_chip(&self) -> Option<&ChipId> {
+        self.pending.front()
+    }
+
+    /// Whether the debugger stopped in the middle of a tick.
+    pub fn in_tick(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Get the line a chip runs next and its locals, if it runs a script.
+    pub fn state(&self, chip: &ChipId) -> Option<RunnerState> {
+        Some(self.yolol_chip(chip)?.runner()?.save_state())
+    }
+
+    /// Run until a breakpoint, a watchpoint or an error stops the simulation, or until
+    /// `ticks` ticks were completed.
+    pub fn resume(&mut self, ticks: u64) -> Stop {
+        let mut completed = 0;
+        while completed < ticks {
+            if let Some(stop) = self.step_next() {
+                return stop;
+            }
+            if !self.in_tick() {
+                completed += 1;
+            }
+        }
+        Stop::Done
+    }
+
+    /// Run the rest of the current tick, or a whole tick between ticks, unless something
+    /// stops it first.
+    pub fn step_tick(&mut self) -> Stop {
+        self.resume(1)
+    }
+
+    /// Run one line of `chip` alone, on the latest globals, while every other chip and the
+    /// tick stay frozen. Its writes reach the devices right away.
+    pub fn step_line(&mut self, chip: &ChipId) -> Option<Stop> {
+        self.pending.retain(|c| c != chip);
+        self.resume = None;
+        let network = self.networks.network_mut(&chip.network)?;
+        let outcome = network.step_chip(chip.device, chip.slot);
+        network.commit();
+        if let Some(outcome) = outcome {
+            if let Some(error) = outcome.error {
+                return Some(Stop::Error {
+                    chip: chip.clone(),
+                    line: outcome.line,
+                    error,
+                });
+            }
+        }
+        self.check_watchpoints()
+    }
+
+    /// Run the next chip of the tick, starting a tick first if needed.
+    fn step_next(&mut self) -> Option<Stop> {
+        if self.pending.is_empty() {
+            self.networks.begin_tick();
+            self.pending = self.networks.chips().into();
+        }
+        if let Some(chip) = self.pending.front().cloned() {
+            if self.resume.take().as_ref() != Some(&chip) {
+                if let Some(line) = self.breakpoint_hit(&chip) {
+                    self.resume = Some(chip.clone());
+                    return Some(Stop::Breakpoint { chip, line });
+                }
+            }
+            self.pending.pop_front();
+            if let Some(outcome) = self.networks.step_chip(&chip) {
+                if let Some(error) = outcome.error {
+                    if self.break_on_error {
+                        if self.pending.is_empty() {
+                            self.networks.end_tick();
+                        }
+                        return Some(Stop::Error {
+                            chip,
+                            line: outcome.line,
+                            error,
+                        });
+                    }
+                }
+            }
+        }
+        if self.pending.is_empty() {
+            self.networks.end_tick();
+        }
+        self.check_watchpoints()
+    }
+
+    /// Get the line `chip` is about to run if a breakpoint stops it there.
+    fn breakpoint_hit(&self, chip: &ChipId) -> Option<usize> {
+        let network = self.networks.network(&chip.network)?;
+        match network.devices().get(chip.device)? {
+            Device::Rack(rack) if rack.powered() => (),
+            _ => return None,
+        }
+        let yolol = self.yolol_chip(chip)?;
+        if **yolol.chip_wait() != YololValue::default() {
+            return None;
+        }
+        let path = yolol.path()?;
+        let state = yolol.runner()?.save_state();
+        let hit = self.breakpoints.iter().any(|b| {
+            if b.path != path || b.line != state.line {
+                return false;
+            }
+            let condition = match &b.condition {
+                Some(condition) => condition,
+                None => return true,
+            };
+            let mut env = network.environment();
+            env.locals = state.locals.clone();
+            matches!(env.eval(condition), Ok(YololValue::Int(v)) if bool::from(&v))
+        });
+        if hit {
+            Some(state.line)
+        } else {
+            None
+        }
+    }
+
+    fn check_watchpoints(&mut self) -> Option<Stop> {
+        let keys: Vec<_> = self.watchpoints.keys().cloned().collect();
+        for (network, field) in keys {
+            let new = self.field(&network, &field);
+            let old = self
+                .watchpoints
+                .insert((network.clone(), field.clone()), new.clone())
+                .flatten();
+            if old != new {
+                return Some(Stop::Watchpoint {
+                    network,
+                    field,
+                    old: old.unwrap_or_default(),
+                    new: new.unwrap_or_default(),
+                });
+            }
+        }
+        None
+    }
+
+    fn field(&self, network: &str, field: &str) -> Option<YololValue> {
+        self.networks.network(network)?.get_field(field).cloned()
+    }
+
+    fn yolol_chip(&self, chip: &ChipId) -> Option<&YololChip<R>> {
+        match self
+            .networks
+            .network(&chip.network)?
+            .devices()
+            .get(chip.device)?
+        {
+            Device::Rack(rack) => match rack.chip(chip.slot)? {
+                Chip::Yolol(yolol) => Some(yolol),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+fn debugger(scripts: &[&str]) -> Debugger<crate::yolol::YololRunner> {
+    Debugger::new(crate::test_network(scripts))
+}
+
+#[cfg(test)]
+fn chip(device: usize) -> ChipId {
+    ChipId {
+        network: "network".to_string(),
+        device,
+        slot: 0,
+    }
+}
+
+#[test]
+fn breakpoint_test() {
+    let mut debugger = debugger(&["i++\nj++ goto 1", "k++ goto 1"]);
+    let path = debugger
+        .yolol_chip(&chip(0))
+        .unwrap()
+        .path()
+        .unwrap()
+        .to_string();
+    debugger.add_breakpoint(Breakpoint {
+        path,
+        line: 2,
+        condition: Some(crate::yolol::parse_expr("i == 2").unwrap()),
+    });
+    assert_eq!(
+        debugger.resume(100),
+        Stop::Breakpoint {
+            chip: chip(0),
+            line: 2
+        }
+    );
+    assert_eq!(debugger.networks().tick(), 3);
+    assert_eq!(debugger.next_chip(), Some(&chip(0)));
+    assert_eq!(debugger.state(&chip(0)).unwrap().locals["i"], 2.into());
+    assert_eq!(debugger.state(&chip(1)).unwrap().locals["k"], 3.into());
+
+    debugger.step_tick();
+    assert!(!debugger.in_tick());
+    assert_eq!(debugger.state(&chip(1)).unwrap().locals["k"], 4.into());
+    assert_eq!(debugger.resume(3), Stop::Done);
+}
+
+#[test]
+fn watchpoint_test() {
+    let mut debugger = debugger(&["b = 1\n:a = 5", "c = 1"]);
+    debugger.add_watchpoint("network", ":A");
+    let stop = debugger.resume(10);
+    assert_eq!(
+        stop,
+        Stop::Watchpoint {
+            network: "network".to_string(),
+            field: "a".to_string(),
+            old: 0.into(),
+            new: 5.into(),
+        }
+    );
+    assert_eq!(debugger.networks().tick(), 2);
+}
+
+#[test]
+fn step_line_test() {
+    let mut debugger = debugger(&[":a++ goto 1", "k++"]);
+    debugger.set_break_on_error(true);
+    debugger.step_line(&chip(0));
+    debugger.step_line(&chip(0));
+    assert_eq!(debugger.networks().tick(), 0);
+    assert_eq!(
+        *debugger
+            .networks()
+            .network("network")
+            .unwrap()
+            .get_field("a")
+            .unwrap(),
+        2.into()
+    );
+    assert!(debugger.state(&chip(1)).unwrap().locals.is_empty());
+}
@@ -1,6 +1,8 @@
 use std::collections::BTreeMap;
 use std::ops::Index;
 
+use crate::value::YololValue;
+
 pub trait Deserializer<D>: Index<String>
 where
     D: Deserializer<D>,
@@ -12,6 +14,26 @@ where
     fn as_str(&self) -> Option<&str>;
 }
 
+/// Read the initial value of a field entry, `{name: Lumens, value: 5000}`. The value is a
+/// number if it reads as one and a string otherwise, unless `type: number` or
+/// `type: string` says otherwise. `None` for a `type: number` value that is not one, which
+/// leaves the field at its default.
+pub fn deserialize_field_value<D>(deserializer: &D) -> Option<YololValue>
+where
+    D: Deserializer<D>,
+{
+    let value = deserializer.get_field("value")?.as_str()?;
+    let value_type = deserializer.get_field("type").and_then(|t| t.as_str());
+    match value_type {
+        Some("string") => Some(value.into()),
+        Some("number") => match YololValue::parse_literal(value) {
+            Ok(YololValue::Int(v)) => Some(v.into()),
+            _ => None,
+        },
+        _ => Some(YololValue::parse_literal(value).unwrap_or_else(|_| value.into())),
+    }
+}
+
 impl<D> Index<String> for &dyn Deserializer<D, Output = D>
 where
     D: Deserializer<D> + Index<String>,
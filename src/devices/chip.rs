@@ -1,11 +1,19 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fmt::Formatter;
 use std::ops::Deref;
 
 use crate::deserialize_field_name;
 use crate::deserializer::Deserializer;
 use crate::field::Field;
+use crate::value::YololError;
+use crate::value::YololValue;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub enum Chip<R: CodeRunner + Default> {
+    #[default]
     None,
     Memory(MemoryChip),
     Yolol(YololChip<R>),
@@ -46,27 +54,68 @@ impl<R: CodeRunner + Default> Chip<R> {
         }
     }*/
 
-    pub fn load(&mut self) {
-        if let Self::Yolol(chip) = self {
-            if let Some(path) = chip.path.clone() {
-                let mut runner = R::default();
-                if runner.parse(&path).is_some() {
-                    chip.runner = Some(runner);
-                } else {
-                    println!("cannot find file {}", path);
-                }
+    pub fn kind(&self) -> Option<ChipKind> {
+        match self {
+            Self::None => None,
+            Self::Memory(_) => Some(ChipKind::Memory),
+            Self::Yolol(_) => Some(ChipKind::Yolol),
+        }
+    }
+
+    /// Load the chip script, returning what is wrong with it. Violations of the in-game
+    /// chip limits prevent the script from being loaded when `strict` is set.
+    pub fn load(&mut self, strict: bool) -> Vec<(String, ChipViolation)> {
+        let chip = match self {
+            Self::Yolol(chip) => chip,
+            _ => return vec![],
+        };
+        let path = match chip.path.clone() {
+            Some(path) => path,
+            None => return vec![],
+        };
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) => return vec![(path, ChipViolation::Unreadable(e.to_string()))],
+        };
+        let mut violations: Vec<_> = validate_source(&source)
+            .into_iter()
+            .map(|violation| (path.clone(), violation))
+            .collect();
+        if strict && !violations.is_empty() {
+            return violations;
+        }
+        let mut runner = R::default();
+        if runner.parse(&source).is_some() {
+            for (line, message) in runner.parse_errors() {
+                violations.push((path.clone(), ChipViolation::Syntax { line, message }));
             }
+            chip.runner = Some(runner);
         }
+        violations
     }
 
-    pub fn step(&mut self) {
+    /// Run one line of the chip. The chip runs while its chip wait is 0. A positive chip wait
+    /// skips the tick and counts down by one, and a negative one pauses the chip until it is
+    /// set back.
+    pub fn step(&mut self) -> Option<StepOutcome> {
         if let Self::Yolol(chip) = self {
             if chip.chip_wait.deref().into() {
+                if let YololValue::Int(wait) = chip.chip_wait.deref() {
+                    if wait > &0.into() {
+                        *chip.chip_wait = (wait - &1.into()).into();
+                    }
+                }
+                // A skipped chip writes nothing, not even what it wrote on its last run.
                 if let Some(runner) = &mut chip.runner {
-                    runner.step()
+                    runner.clear_written();
                 }
+                return None;
+            }
+            if let Some(runner) = &mut chip.runner {
+                return Some(runner.step());
             }
         }
+        None
     }
 
     pub fn update_globals(&mut self, globals: Vec<Field>) {
@@ -87,27 +136,205 @@ impl<R: CodeRunner + Default> Chip<R> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChipKind {
+    Memory,
+    Yolol,
+}
+
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MemoryChip {}
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub struct YololChip<R: CodeRunner + Default> {
     chip_wait: Field,
     path: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     runner: Option<R>,
 }
 
-impl<'a, R: CodeRunner + Default> Default for Chip<R> {
-    fn default() -> Self {
-        Self::None
+impl<R: CodeRunner + Default> YololChip<R> {
+    /// Get a reference to the chip's script path.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Set the chip's script path. The script is loaded on the next `Chip::load`.
+    pub fn set_path(&mut self, path: Option<String>) {
+        self.path = path;
+        self.runner = None;
+    }
+
+    /// Get a reference to the chip's runner, if its script is loaded.
+    pub fn runner(&self) -> Option<&R> {
+        self.runner.as_ref()
+    }
+
+    /// Get a mutable reference to the chip's runner, if its script is loaded.
+    pub fn runner_mut(&mut self) -> Option<&mut R> {
+        self.runner.as_mut()
+    }
+
+    /// Get a reference to the chip's chip wait field.
+    pub fn chip_wait(&self) -> &Field {
+        &self.chip_wait
+    }
+
+    /// Get a mutable reference to the chip's chip wait field.
+    pub fn chip_wait_mut(&mut self) -> &mut Field {
+        &mut self.chip_wait
+    }
+}
+
+pub const CHIP_MAX_LINES: usize = 20;
+pub const CHIP_MAX_LINE_LENGTH: usize = 70;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChipViolation {
+    TooManyLines(usize),
+    LineTooLong {
+        line: usize,
+        length: usize,
+    },
+    NonAscii {
+        line: usize,
+        column: usize,
+        character: char,
+    },
+    /// The script could not be read.
+    Unreadable(String),
+    /// A line the runner could not parse.
+    Syntax {
+        line: usize,
+        message: String,
+    },
+}
+
+impl Display for ChipViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChipViolation::TooManyLines(count) => write!(
+                f,
+                "{} lines, a chip holds at most {}",
+                count, CHIP_MAX_LINES
+            ),
+            ChipViolation::LineTooLong { line, length } => write!(
+                f,
+                "line {} is {} characters long, a chip line holds at most {}",
+                line, length, CHIP_MAX_LINE_LENGTH
+            ),
+            ChipViolation::NonAscii {
+                line,
+                column,
+                character,
+            } => write!(
+                f,
+                "line {} column {}: non-ASCII character {:?}",
+                line, column, character
+            ),
+            ChipViolation::Unreadable(error) => write!(f, "cannot read the script: {}", error),
+            ChipViolation::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+/// Check a script against the in-game chip limits. Lines and columns are 1-based.
+pub fn validate_source(source: &str) -> Vec<ChipViolation> {
+    let mut violations = vec![];
+    let lines: Vec<&str> = source.lines().collect();
+    if lines.len() > CHIP_MAX_LINES {
+        violations.push(ChipViolation::TooManyLines(lines.len()));
+    }
+    for (i, line) in lines.iter().enumerate() {
+        let length = line.chars().count();
+        if length > CHIP_MAX_LINE_LENGTH {
+            violations.push(ChipViolation::LineTooLong {
+                line: i + 1,
+                length,
+            });
+        }
+        for (column, character) in line.chars().enumerate() {
+            if !character.is_ascii() {
+                violations.push(ChipViolation::NonAscii {
+                    line: i + 1,
+                    column: column + 1,
+                    character,
+                });
+            }
+        }
     }
+    violations
+}
+
+/// Why a line stopped running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuntimeError {
+    /// An operation on values failed.
+    Value(YololError),
+    /// A statement got a value of the wrong type, like `goto` with a string.
+    TypeMismatch,
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeError::Value(error) => error.fmt(f),
+            RuntimeError::TypeMismatch => write!(f, "type mismatch"),
+        }
+    }
+}
+
+impl From<YololError> for RuntimeError {
+    fn from(error: YololError) -> Self {
+        RuntimeError::Value(error)
+    }
+}
+
+/// What happened when a runner ran a line. `line` is 1-based.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StepOutcome {
+    pub line: usize,
+    pub error: Option<RuntimeError>,
+}
+
+impl StepOutcome {
+    pub fn errored(&self) -> bool {
+        self.error.is_some()
+    }
+}
+
+/// The state of a runner between two lines. `line` is the 1-based line to run next.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RunnerState {
+    pub line: usize,
+    pub locals: BTreeMap<String, YololValue>,
 }
 
 pub trait CodeRunner: Default {
-    fn parse(&mut self, path: &str) -> Option<()>;
-    fn step(&mut self);
+    /// Load a script from its source. `None` if the runner cannot run it.
+    fn parse(&mut self, source: &str) -> Option<()>;
+
+    /// Get the lines the last `parse` could not read, with why, by 1-based line.
+    fn parse_errors(&self) -> Vec<(usize, String)> {
+        vec![]
+    }
+
+    fn step(&mut self) -> StepOutcome;
     fn update_globals(&mut self, globals: Vec<Field>);
     fn get_global(&self) -> Vec<Field>;
+
+    /// Forget the globals written by the last `step`, for a tick the chip does not run.
+    fn clear_written(&mut self) {}
+
+    fn save_state(&self) -> RunnerState {
+        RunnerState::default()
+    }
+
+    fn load_state(&mut self, _: &RunnerState) {}
 }
 
 #[derive(Default, Debug)]
@@ -116,7 +343,9 @@ impl CodeRunner for NoneRunner {
     fn parse(&mut self, _: &str) -> Option<()> {
         None
     }
-    fn step(&mut self) {}
+    fn step(&mut self) -> StepOutcome {
+        StepOutcome::default()
+    }
 
     fn update_globals(&mut self, _: Vec<Field>) {}
 
@@ -124,3 +353,104 @@ impl CodeRunner for NoneRunner {
         vec![]
     }
 }
+
+#[test]
+fn validate_source_test_ok() {
+    let source = "a=1\n".repeat(CHIP_MAX_LINES);
+    assert!(validate_source(&source).is_empty())
+}
+
+#[test]
+fn validate_source_test_limits() {
+    let mut source = "a=1\n".repeat(CHIP_MAX_LINES);
+    source.push_str(&"b".repeat(CHIP_MAX_LINE_LENGTH + 1));
+    source.push_str("\n:s=\"é\"");
+    assert_eq!(
+        validate_source(&source),
+        vec![
+            ChipViolation::TooManyLines(22),
+            ChipViolation::LineTooLong {
+                line: 21,
+                length: 71
+            },
+            ChipViolation::NonAscii {
+                line: 22,
+                column: 5,
+                character: 'é'
+            },
+        ]
+    )
+}
+
+#[test]
+fn load_test() {
+    use crate::yolol::YololRunner;
+
+    let dir = crate::TestDir::new();
+    let chip = |path: &str| {
+        Chip::<YololRunner>::Yolol(YololChip {
+            path: Some(path.to_string()),
+            ..YololChip::default()
+        })
+    };
+    let path = dir.write("syntax.yolol", "a = 1\nb = (");
+    let mut syntax = chip(&path);
+    let violations = syntax.load(true);
+    assert!(matches!(
+        violations.as_slice(),
+        [(p, ChipViolation::Syntax { line: 2, .. })] if *p == path
+    ));
+    assert!(matches!(
+        syntax,
+        Chip::Yolol(YololChip {
+            runner: Some(_),
+            ..
+        })
+    ));
+
+    let mut long = chip(&dir.write("long.yolol", &"a".repeat(CHIP_MAX_LINE_LENGTH + 1)));
+    assert_eq!(long.load(true).len(), 1);
+    assert!(matches!(long, Chip::Yolol(YololChip { runner: None, .. })));
+
+    let missing = dir.path().join("missing.yolol");
+    let violations = chip(&missing.to_string_lossy()).load(false);
+    assert!(matches!(
+        violations.as_slice(),
+        [(_, ChipViolation::Unreadable(_))]
+    ));
+}
+
+#[test]
+fn chip_wait_test() {
+    use crate::yolol::YololRunner;
+
+    let mut runner = YololRunner::default();
+    runner.load_source("a++");
+    let mut chip = Chip::Yolol(YololChip {
+        runner: Some(runner),
+        ..YololChip::default()
+    });
+    let wait = |chip: &mut Chip<YololRunner>, value: Option<i64>| match chip {
+        Chip::Yolol(chip) => {
+            if let Some(value) = value {
+                *chip.chip_wait = value.into();
+            }
+            (*chip.chip_wait).clone()
+        }
+        _ => unreachable!(),
+    };
+    assert!(chip.step().is_some());
+
+    wait(&mut chip, Some(2));
+    assert!(chip.step().is_none());
+    assert_eq!(wait(&mut chip, None), 1.into());
+    assert!(chip.step().is_none());
+    assert_eq!(wait(&mut chip, None), 0.into());
+    assert!(chip.step().is_some());
+
+    wait(&mut chip, Some(-1));
+    for _ in 0..3 {
+        assert!(chip.step().is_none());
+    }
+    assert_eq!(wait(&mut chip, None), (-1).into());
+}
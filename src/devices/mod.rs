@@ -9,6 +9,8 @@ use enum_dispatch::enum_dispatch;
 
 use self::chip::CodeRunner;
 pub use self::rack::Rack;
+pub use self::rack::RackModuleKind;
+pub use self::rack::RACK_MODULES;
 use crate::deserializer::Deserializer;
 use crate::field::Field;
 use crate::value::YololValue;
@@ -19,6 +21,8 @@ use crate::value::YololValue;
 pub trait DeviceTrait {
     fn get_field(&self, field: &str) -> Option<&YololValue>;
     fn get_field_mut(&mut self, field: &str) -> Option<&mut YololValue>;
+    fn get_fields(&self) -> Vec<&Field>;
+    fn get_fields_mut(&mut self) -> Vec<&mut Field>;
     fn get_device_name(&self) -> String;
     fn deserialize<D>(&mut self, deserializer: &D)
     where
@@ -28,6 +32,8 @@ pub trait DeviceTrait {
 #[allow(clippy::large_enum_variant)]
 #[enum_dispatch(DeviceTrait)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub enum Device<R: CodeRunner + Default> {
     Button(Button),
     CargoBeam(CargoBeam),
@@ -64,7 +70,6 @@ impl<R: CodeRunner + Default> Device<R> {
         let device_type = deserializer
             .get_type()
             .expect("Need a type for deserializing");
-        println!("trying to deserialize {}", device_type);
 
         let device: Option<Device<R>> = match device_type.as_str() {
             "!button" => Some(Button::default().into()),
@@ -104,24 +109,31 @@ impl<R: CodeRunner + Default> Device<R> {
     }
 }
 
+/// Read the name and initial value of a field. A field is either given as its name,
+/// `LampLumens: Lumens`, or as a map, `LampLumens: {name: Lumens, value: 5000}`.
 #[macro_export]
 macro_rules! deserialize_field_name {
     ($device:ident, $name:ident, $deserializer:ident) => {{
         use convert_case::Case;
         use convert_case::Casing;
         let name = stringify!($name).to_case(Case::Pascal);
+        let entry = $deserializer.get_field(&name);
         $device.$name.set_name(
-            $deserializer[name.to_string()]
-                .as_str()
+            entry
+                .and_then(|entry| entry.as_str().or_else(|| entry.get_field("name")?.as_str()))
                 .unwrap_or(name.as_str())
                 .to_string(),
         );
+        if let Some(value) = entry.and_then($crate::deserializer::deserialize_field_value) {
+            *$device.$name = value;
+        }
     }};
 }
 
 macro_rules! make_device {
     ($name:ident $(, $field:ident)+ $(,)?) => {
         #[derive(Debug, Default)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
         pub struct $name {
             $($field:Field,)+
         }
@@ -169,6 +181,14 @@ macro_rules! make_device {
                 )+
                 None
             }
+
+            fn get_fields(&self) -> Vec<&Field> {
+                vec![$(&self.$field,)+]
+            }
+
+            fn get_fields_mut(&mut self) -> Vec<&mut Field> {
+                vec![$(&mut self.$field,)+]
+            }
         }
     };
 }
@@ -361,3 +381,28 @@ make_device!(
     min_rotation,
     target_velocity
 );
+
+#[cfg(feature = "yaml")]
+#[test]
+fn field_value_test() {
+    let node = crate::yaml::YamlNode::parse(
+        "!lamp
+LampOn: Light
+LampLumens:
+  name: Lumens
+  value: 5000
+LampColorHue:
+  value: 120
+  type: string
+LampRange:
+  value: far
+  type: number
+",
+    )
+    .unwrap();
+    let device = Device::<chip::NoneRunner>::deserialize(&node).unwrap();
+    assert_eq!(device.get_field("light"), Some(&0.into()));
+    assert_eq!(device.get_field("lumens"), Some(&5000.into()));
+    assert_eq!(device.get_field("lampcolorhue"), Some(&"120".into()));
+    assert_eq!(device.get_field("lamprange"), Some(&0.into()));
+}
@@ -1,13 +1,17 @@
 use std::ops::Deref;
 
 use super::chip::Chip;
+use super::chip::ChipViolation;
 use super::chip::CodeRunner;
+use super::chip::StepOutcome;
 use super::DeviceTrait;
 use crate::deserialize_field_name;
 use crate::deserializer::Deserializer;
 use crate::field::Field;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub struct Rack<R: CodeRunner + Default> {
     current_state: Field,
     on_state: Field,
@@ -17,80 +21,211 @@ pub struct Rack<R: CodeRunner + Default> {
     module: RackModule<R>,
 }
 
+impl<R: CodeRunner + Default> Default for Rack<R> {
+    fn default() -> Self {
+        let mut rack = Self {
+            current_state: Field::default(),
+            on_state: Field::default(),
+            off_state: Field::default(),
+            button: Field::default(),
+            module: RackModule::default(),
+        };
+        *rack.current_state = 1.into();
+        *rack.on_state = 1.into();
+        rack
+    }
+}
+
 impl<R: CodeRunner + Default> Rack<R> {
-    pub fn parse_all_chip_file(&mut self) {
-        match &mut self.module {
-            RackModule::Core(a, b, c) => {
-                a.load();
-                b.load();
-                c.load()
-            }
-            RackModule::Socket(a, b) => {
-                a.load();
-                b.load()
-            }
-            RackModule::Reader(a) => a.load(),
+    /// A rack powers its module while its current state is its on state.
+    pub fn powered(&self) -> bool {
+        *self.current_state == *self.on_state
+    }
+
+    /// Toggle the rack between its on and off state when its button is pressed. The button
+    /// is released afterwards.
+    pub fn update_state(&mut self) {
+        if self.button.deref().into() {
+            let state = if self.powered() {
+                self.off_state.deref().clone()
+            } else {
+                self.on_state.deref().clone()
+            };
+            *self.current_state = state;
+            *self.button = 0.into();
         }
     }
 
-    pub fn step(&mut self) {
-        match &mut self.module {
-            RackModule::Core(a, b, c) => {
-                a.step();
-                b.step();
-                c.step()
-            }
-            RackModule::Socket(a, b) => {
-                a.step();
-                b.step()
+    /// Get the kind of module plugged into the rack.
+    pub fn module_kind(&self) -> &'static RackModuleKind {
+        self.module.kind
+    }
+
+    /// Get the number of chip slots of the rack's module.
+    pub fn slots(&self) -> usize {
+        self.module.chips.len()
+    }
+
+    /// Get a reference to the chip in `slot`, slots being numbered from 0.
+    pub fn chip(&self, slot: usize) -> Option<&Chip<R>> {
+        self.module.chips.get(slot)
+    }
+
+    /// Get a mutable reference to the chip in `slot`, slots being numbered from 0.
+    pub fn chip_mut(&mut self, slot: usize) -> Option<&mut Chip<R>> {
+        self.module.chips.get_mut(slot)
+    }
+
+    pub fn chips(&self) -> impl Iterator<Item = &Chip<R>> {
+        self.module.chips.iter()
+    }
+
+    pub fn chips_mut(&mut self) -> impl Iterator<Item = &mut Chip<R>> {
+        self.module.chips.iter_mut()
+    }
+
+    /// Put `chip` into `slot` and return the chip it replaces. The chip is handed back as
+    /// an error if the slot does not exist.
+    pub fn insert_chip(&mut self, slot: usize, chip: Chip<R>) -> Result<Chip<R>, Chip<R>> {
+        match self.module.chips.get_mut(slot) {
+            Some(old) => Ok(std::mem::replace(old, chip)),
+            None => Err(chip),
+        }
+    }
+
+    /// Take the chip out of `slot`, leaving it empty.
+    pub fn remove_chip(&mut self, slot: usize) -> Option<Chip<R>> {
+        self.module.chips.get_mut(slot).map(std::mem::take)
+    }
+
+    /// Swap the chips of two slots of the rack.
+    pub fn swap_chips(&mut self, a: usize, b: usize) -> Option<()> {
+        if a >= self.slots() || b >= self.slots() {
+            return None;
+        }
+        self.module.chips.swap(a, b);
+        Some(())
+    }
+
+    pub fn parse_all_chip_file(&mut self, strict: bool) -> Vec<(String, ChipViolation)> {
+        let mut violations = vec![];
+        for chip in &mut self.module.chips {
+            violations.append(&mut chip.load(strict));
+        }
+        violations
+    }
+
+    /// Run the chips of the rack, returning the outcome of each chip that ran by slot.
+    pub fn step(&mut self) -> Vec<(usize, StepOutcome)> {
+        if !self.powered() {
+            return vec![];
+        }
+        let mut outcomes = vec![];
+        for (slot, chip) in self.module.chips.iter_mut().enumerate() {
+            if let Some(outcome) = chip.step() {
+                outcomes.push((slot, outcome));
             }
-            RackModule::Reader(a) => a.step(),
         }
+        outcomes
     }
 
     pub fn update_globals(&mut self, globals: Vec<Field>) {
-        match &mut self.module {
-            RackModule::Core(a, b, c) => {
-                a.update_globals(globals.clone());
-                b.update_globals(globals.clone());
-                c.update_globals(globals)
-            }
-            RackModule::Socket(a, b) => {
-                a.update_globals(globals.clone());
-                b.update_globals(globals)
-            }
-            RackModule::Reader(a) => a.update_globals(globals),
+        for chip in &mut self.module.chips {
+            chip.update_globals(globals.clone());
         }
     }
 
     pub fn get_global(&self) -> Vec<Field> {
-        match &self.module {
-            RackModule::Core(a, b, c) => {
-                let mut a = a.get_global();
-                a.append(&mut b.get_global());
-                a.append(&mut c.get_global());
-                a
-            }
-            RackModule::Socket(a, b) => {
-                let mut a = a.get_global();
-                a.append(&mut b.get_global());
-                a
-            }
-            RackModule::Reader(a) => a.get_global(),
+        if !self.powered() {
+            return vec![];
         }
+        let mut globals = vec![];
+        for chip in &self.module.chips {
+            globals.append(&mut chip.get_global());
+        }
+        globals
+    }
+}
+
+/// Describes a module that can be plugged into a rack.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RackModuleKind {
+    pub name: &'static str,
+    pub tags: &'static [&'static str],
+    pub slots: usize,
+}
+
+/// Every known rack module. New modules only need an entry here.
+pub static RACK_MODULES: &[RackModuleKind] = &[
+    RackModuleKind {
+        name: "chip_core",
+        tags: &["!chip_core"],
+        slots: 3,
+    },
+    RackModuleKind {
+        name: "socket_core",
+        tags: &["!socket_core", "!chip_socket_core", "!socker_core"],
+        slots: 2,
+    },
+    RackModuleKind {
+        name: "chip_reader",
+        tags: &["!chip_reader"],
+        slots: 1,
+    },
+];
+
+impl RackModuleKind {
+    /// Find the module registered under `tag`.
+    pub fn from_tag(tag: &str) -> Option<&'static Self> {
+        RACK_MODULES.iter().find(|kind| kind.tags.contains(&tag))
     }
 }
 
 #[derive(Debug)]
-enum RackModule<R: CodeRunner + Default> {
-    Core(Chip<R>, Chip<R>, Chip<R>),
-    Socket(Chip<R>, Chip<R>),
-    Reader(Chip<R>),
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
+struct RackModule<R: CodeRunner + Default> {
+    #[cfg_attr(feature = "serde", serde(with = "module_kind"))]
+    kind: &'static RackModuleKind,
+    chips: Vec<Chip<R>>,
+}
+
+impl<R: CodeRunner + Default> RackModule<R> {
+    fn new(kind: &'static RackModuleKind) -> Self {
+        Self {
+            kind,
+            chips: (0..kind.slots).map(|_| Chip::None).collect(),
+        }
+    }
+}
+
+/// Rack modules are serialized by name.
+#[cfg(feature = "serde")]
+mod module_kind {
+    use super::RackModuleKind;
+    use super::RACK_MODULES;
+
+    pub fn serialize<S: serde::Serializer>(
+        kind: &&'static RackModuleKind,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(kind.name)
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<&'static RackModuleKind, D::Error> {
+        let name: String = serde::Deserialize::deserialize(deserializer)?;
+        RACK_MODULES
+            .iter()
+            .find(|kind| kind.name == name)
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown rack module {}", name)))
+    }
 }
 
 impl<R: CodeRunner + Default> Default for RackModule<R> {
     fn default() -> Self {
-        Self::Reader(Chip::default())
+        Self::new(RackModuleKind::from_tag("!chip_reader").unwrap())
     }
 }
 
@@ -103,7 +238,7 @@ impl<R: CodeRunner + Default> DeviceTrait for Rack<R> {
             return Some(&self.on_state);
         }
         if self.off_state.name() == field {
-            return Some(&self.current_state);
+            return Some(&self.off_state);
         }
         if self.button.name() == field {
             return Some(&self.button);
@@ -119,7 +254,7 @@ impl<R: CodeRunner + Default> DeviceTrait for Rack<R> {
             return Some(&mut self.on_state);
         }
         if self.off_state.name() == field {
-            return Some(&mut self.current_state);
+            return Some(&mut self.off_state);
         }
         if self.button.name() == field {
             return Some(&mut self.button);
@@ -127,6 +262,24 @@ impl<R: CodeRunner + Default> DeviceTrait for Rack<R> {
         None
     }
 
+    fn get_fields(&self) -> Vec<&Field> {
+        vec![
+            &self.current_state,
+            &self.on_state,
+            &self.off_state,
+            &self.button,
+        ]
+    }
+
+    fn get_fields_mut(&mut self) -> Vec<&mut Field> {
+        vec![
+            &mut self.current_state,
+            &mut self.on_state,
+            &mut self.off_state,
+            &mut self.button,
+        ]
+    }
+
     fn get_device_name(&self) -> String {
         "rack".to_string()
     }
@@ -142,45 +295,84 @@ impl<R: CodeRunner + Default> DeviceTrait for Rack<R> {
 
         if let Some(tag) = deserializer["module".to_string()].get_type() {
             let modules = &deserializer["module".to_string()];
-            match tag.as_str() {
-                "!socker_core" => {
-                    let chip1 = modules["slot1".to_string()]
-                        .get_type()
-                        .map(|tag| Chip::deserialize(tag, &modules["slot1".to_string()]))
-                        .unwrap_or(Chip::None);
-                    let chip2 = modules["slot2".to_string()]
-                        .get_type()
-                        .map(|tag| Chip::deserialize(tag, &modules["slot2".to_string()]))
-                        .unwrap_or(Chip::None);
-                    let rack_module = RackModule::Socket(chip1, chip2);
-                    self.module = rack_module;
-                }
-                "!chip_core" => {
-                    let chip1 = modules["slot1".to_string()]
-                        .get_type()
-                        .map(|tag| Chip::deserialize(tag, &modules["slot1".to_string()]))
-                        .unwrap_or(Chip::None);
-                    let chip2 = modules["slot2".to_string()]
-                        .get_type()
-                        .map(|tag| Chip::deserialize(tag, &modules["slot2".to_string()]))
-                        .unwrap_or(Chip::None);
-                    let chip3 = modules["slot3".to_string()]
-                        .get_type()
-                        .map(|tag| Chip::deserialize(tag, &modules["slot3".to_string()]))
-                        .unwrap_or(Chip::None);
-                    let rack_module = RackModule::Core(chip1, chip2, chip3);
-                    self.module = rack_module;
-                }
-                "!chip_reader" => {
-                    let chip1 = modules["slot1".to_string()]
-                        .get_type()
-                        .map(|tag| Chip::deserialize(tag, &modules["slot1".to_string()]))
-                        .unwrap_or(Chip::None);
-                    let rack_module = RackModule::Reader(chip1);
-                    self.module = rack_module;
+            // An unknown module leaves the rack without one.
+            if let Some(kind) = RackModuleKind::from_tag(&tag) {
+                let mut module = RackModule::new(kind);
+                for (i, chip) in module.chips.iter_mut().enumerate() {
+                    let slot = &modules[format!("slot{}", i + 1)];
+                    if let Some(tag) = slot.get_type() {
+                        *chip = Chip::deserialize(tag, slot);
+                    }
                 }
-                _ => (),
+                self.module = module;
             }
         }
     }
 }
+
+#[test]
+fn button_test() {
+    use super::chip::NoneRunner;
+
+    let mut rack = Rack::<NoneRunner>::default();
+    assert!(rack.powered());
+    rack.update_state();
+    assert!(rack.powered());
+
+    *rack.button = 1.into();
+    rack.update_state();
+    assert!(!rack.powered());
+    assert_eq!(*rack.button, 0.into());
+
+    *rack.button = 1.into();
+    rack.update_state();
+    assert!(rack.powered());
+}
+
+#[test]
+fn module_tag_test() {
+    assert_eq!(RackModuleKind::from_tag("!chip_core").unwrap().slots, 3);
+    assert_eq!(
+        RackModuleKind::from_tag("!socket_core"),
+        RackModuleKind::from_tag("!socker_core")
+    );
+    assert_eq!(RackModuleKind::from_tag("!socket_core").unwrap().slots, 2);
+    assert!(RackModuleKind::from_tag("!lamp").is_none());
+}
+
+#[test]
+fn chip_slot_test() {
+    use super::chip::ChipKind;
+    use super::chip::NoneRunner;
+
+    let mut rack = Rack::<NoneRunner>::default();
+    assert_eq!(rack.slots(), 1);
+    assert!(rack
+        .insert_chip(0, Chip::Memory(Default::default()))
+        .is_ok());
+    assert!(rack
+        .insert_chip(1, Chip::Memory(Default::default()))
+        .is_err());
+    assert_eq!(rack.chip(0).unwrap().kind(), Some(ChipKind::Memory));
+    assert!(rack.swap_chips(0, 1).is_none());
+    assert_eq!(rack.remove_chip(0).unwrap().kind(), Some(ChipKind::Memory));
+    assert!(rack.chips().all(|chip| chip.kind().is_none()));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_test() {
+    use super::chip::ChipKind;
+    use super::chip::NoneRunner;
+    use super::chip::YololChip;
+
+    let mut rack = Rack::<NoneRunner>::default();
+    let mut chip = YololChip::default();
+    chip.set_path(Some("a.yolol".to_string()));
+    rack.insert_chip(0, Chip::Yolol(chip)).unwrap();
+    let json = serde_json::to_string(&rack).unwrap();
+    let back: Rack<NoneRunner> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.module_kind().name, "chip_reader");
+    assert_eq!(back.chip(0).unwrap().kind(), Some(ChipKind::Yolol));
+    assert!(back.powered());
+}
@@ -3,7 +3,8 @@ use std::ops::DerefMut;
 
 use crate::value::YololValue;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Field {
     name: String,
     value: YololValue,
@@ -1,22 +1,84 @@
 use std::collections::BTreeMap;
 use std::ops::Deref;
 use std::ops::Index;
+#[cfg(feature = "yaml")]
+use std::path::Path;
 
 use deserializer::Deserializer;
+use devices::chip::ChipViolation;
 use devices::chip::CodeRunner;
+use devices::chip::StepOutcome;
 use devices::Device;
+use devices::DeviceTrait;
 use field::Field;
+use record::Input;
+use record::Recording;
+use trace::ChangeSource;
+use trace::FieldChange;
+use value::YololValue;
+use yolol::Environment;
 
+#[cfg(feature = "dap")]
+pub mod dap;
+pub mod debugger;
 pub mod deserializer;
 pub mod devices;
 pub mod field;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+#[cfg(any(feature = "dap", feature = "lsp"))]
+pub mod protocol;
+pub mod record;
+pub mod repl;
+pub mod snapshot;
+pub mod trace;
 pub mod value;
+pub mod vcd;
+#[cfg(feature = "yaml")]
+pub mod yaml;
+pub mod yolol;
 
 #[derive(Debug)]
 pub struct Networks<R: CodeRunner + Default> {
     networks: BTreeMap<String, Network<R>>,
 
     relays: Vec<(String, String)>,
+
+    strict: bool,
+    tick_model: TickModel,
+    tick: u64,
+    recording: Option<Recording>,
+    trace: Option<Vec<FieldChange>>,
+}
+
+/// The outcome of a chip, `device` being the index of its rack in the network's devices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChipOutcome {
+    pub device: usize,
+    pub slot: usize,
+    pub outcome: StepOutcome,
+}
+
+pub type StepReport = BTreeMap<String, Vec<ChipOutcome>>;
+
+/// A chip slot of the simulation, `device` being the index of its rack in the devices of
+/// the network `network`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChipId {
+    pub network: String,
+    pub device: usize,
+    pub slot: usize,
+}
+
+/// How the chips of a network see each other's writes during a tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TickModel {
+    /// Every chip reads the globals of the previous tick, writes are committed once all
+    /// chips ran.
+    #[default]
+    Phased,
+    /// Each chip reads the globals, runs and commits before the next chip runs, as in game.
+    Interleaved,
 }
 
 impl<R: CodeRunner + Default> Networks<R> {
@@ -27,9 +89,6 @@ impl<R: CodeRunner + Default> Networks<R> {
         let mut networks = BTreeMap::new();
         for network in deserializer["networks".to_string()].as_vec()?.iter() {
             let name = network["name".to_string()].as_str()?;
-
-            println!("deserialize network : {}", name);
-
             networks.insert(name.to_string(), Network::deserialize(*network));
         }
         let mut relays = vec![];
@@ -42,35 +101,263 @@ impl<R: CodeRunner + Default> Networks<R> {
                 relays.push((src, dst));
             }
         }
-        Some(Self { networks, relays })
+        Some(Self {
+            networks,
+            relays,
+            strict: false,
+            tick_model: TickModel::default(),
+            tick: 0,
+            recording: None,
+            trace: None,
+        })
     }
 
-    pub fn parse_all_chip_file(&mut self) {
+    /// Read the ship file at `path` and load its chip scripts, returning the networks and
+    /// the problems found in the scripts.
+    #[cfg(feature = "yaml")]
+    pub fn load(
+        path: impl AsRef<Path>,
+        strict: bool,
+    ) -> Result<(Self, Vec<(String, ChipViolation)>), String> {
+        Self::load_in(Path::new(""), path, strict)
+    }
+
+    /// Like `load`, with `path` and the relative script paths taken from `dir`.
+    #[cfg(feature = "yaml")]
+    pub(crate) fn load_in(
+        dir: &Path,
+        path: impl AsRef<Path>,
+        strict: bool,
+    ) -> Result<(Self, Vec<(String, ChipViolation)>), String> {
+        let path = dir.join(path);
+        let name = path.display();
+        let source =
+            std::fs::read_to_string(&path).map_err(|e| format!("cannot read {}: {}", name, e))?;
+        let ship = yaml::YamlNode::parse(&source).map_err(|e| format!("{}: {}", name, e))?;
+        let mut networks =
+            Self::deserialize(&ship).ok_or(format!("{} is not a ship file", name))?;
+        if dir != Path::new("") {
+            networks.resolve_scripts(dir);
+        }
+        networks.set_strict(strict);
+        let violations = networks.parse_all_chip_file();
+        Ok((networks, violations))
+    }
+
+    /// Make the relative script paths of the chips relative to `dir` instead.
+    #[cfg(feature = "yaml")]
+    fn resolve_scripts(&mut self, dir: &Path) {
         for network in self.networks.values_mut() {
-            network.parse_all_chip_file();
+            for device in &mut network.devices {
+                if let Device::Rack(rack) = device {
+                    for chip in rack.chips_mut() {
+                        if let devices::chip::Chip::Yolol(chip) = chip {
+                            let path = chip.path().map(|path| dir.join(path));
+                            chip.set_path(path.map(|path| path.to_string_lossy().into_owned()));
+                        }
+                    }
+                }
+            }
         }
     }
 
-    pub fn step(&mut self) {
+    /// Load every chip script, returning the chip limit violations found on the way.
+    pub fn parse_all_chip_file(&mut self) -> Vec<(String, ChipViolation)> {
+        let mut violations = vec![];
         for network in self.networks.values_mut() {
-            network.update_globals();
+            violations.append(&mut network.parse_all_chip_file(self.strict));
         }
+        violations
+    }
+
+    /// Refuse to load chip scripts that would not fit in a game chip.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn set_tick_model(&mut self, tick_model: TickModel) {
+        self.tick_model = tick_model;
+    }
+
+    /// Run one tick of the simulation.
+    ///
+    /// Networks are visited in name order, devices in ship file order and chips in slot
+    /// order, so a tick always runs the same way. With [`TickModel::Phased`] the tick is
+    /// split in four phases:
+    /// - read: every chip receives a copy of its network's globals,
+    /// - execute: every chip runs one line,
+    /// - commit: the chips' globals are written to their network and its devices,
+    /// - relay: the globals of every relay source, as they were after the commit phase,
+    ///   are copied to the relay destination, in ship file order.
+    ///
+    /// [`TickModel::Interleaved`] merges the first three phases per chip, like the game.
+    ///
+    /// The outcome of every chip that ran is returned by network name.
+    pub fn step(&mut self) -> StepReport {
+        let mut report = StepReport::new();
+        for name in self.networks.keys() {
+            report.insert(name.clone(), vec![]);
+        }
+        self.begin_tick();
+        for chip in self.chips() {
+            if let Some(outcome) = self.step_chip(&chip) {
+                report.get_mut(&chip.network).unwrap().push(ChipOutcome {
+                    device: chip.device,
+                    slot: chip.slot,
+                    outcome,
+                });
+            }
+        }
+        self.end_tick();
+        report
+    }
+
+    /// Every chip slot, in the order a tick runs them.
+    pub fn chips(&self) -> Vec<ChipId> {
+        let mut chips = vec![];
+        for (name, network) in &self.networks {
+            for (device, slot) in network.chip_slots() {
+                chips.push(ChipId {
+                    network: name.clone(),
+                    device,
+                    slot,
+                });
+            }
+        }
+        chips
+    }
+
+    /// Start a tick that is run chip by chip with `step_chip`, then finished with `end_tick`.
+    /// `step` does all three.
+    pub fn begin_tick(&mut self) {
+        if self.tick_model == TickModel::Phased {
+            for network in self.networks.values_mut() {
+                network.update_globals();
+            }
+        }
+    }
+
+    /// Run one line of a chip during a tick, as the tick model runs it. Nothing happens if
+    /// the slot holds no running chip.
+    pub fn step_chip(&mut self, chip: &ChipId) -> Option<StepOutcome> {
+        let network = self.networks.get_mut(&chip.network)?;
+        match self.tick_model {
+            TickModel::Phased => network.run_chip(chip.device, chip.slot),
+            TickModel::Interleaved => network.step_chip(chip.device, chip.slot),
+        }
+    }
+
+    /// Commit the chips' writes, apply the relays and move to the next tick.
+    pub fn end_tick(&mut self) {
         for network in self.networks.values_mut() {
-            network.step();
+            match self.tick_model {
+                TickModel::Phased => network.update(),
+                TickModel::Interleaved => network.commit(),
+            }
         }
+        let relayed: Vec<_> = self
+            .relays
+            .iter()
+            .filter_map(|(src, dst)| Some((src, dst, self.networks.get(src)?.globals())))
+            .collect();
+        for (src, dst, globals) in relayed {
+            if let Some(dst) = self.networks.get_mut(dst) {
+                let changes = merge_globals(&mut dst.globals, globals);
+                dst.record(
+                    changes,
+                    ChangeSource::Relay {
+                        network: src.clone(),
+                    },
+                );
+            }
+        }
+        self.collect_trace();
+        self.tick += 1;
+    }
+
+    /// Log every field change from now on, until `set_tracing(false)`.
+    pub fn set_tracing(&mut self, tracing: bool) {
+        self.trace = if tracing { Some(vec![]) } else { None };
         for network in self.networks.values_mut() {
-            network.update();
+            network.trace = if tracing { Some(vec![]) } else { None };
         }
-        for (src, dst) in &self.relays {
-            if let Some((_, src)) = self.networks.iter().find(|(s, _)| s == &src) {
-                let src = src.globals().clone();
-                if let Some((_, dst)) = self.networks.iter_mut().find(|(s, _)| s == &dst) {
-                    dst.set_globals(src);
+    }
+
+    /// Take the field changes logged since the last call.
+    pub fn take_trace(&mut self) -> Vec<FieldChange> {
+        self.collect_trace();
+        self.trace.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    fn collect_trace(&mut self) {
+        if let Some(trace) = &mut self.trace {
+            for (name, network) in self.networks.iter_mut() {
+                if let Some(changes) = &mut network.trace {
+                    for (field, old, new, source) in changes.drain(..) {
+                        trace.push(FieldChange {
+                            tick: self.tick,
+                            network: name.clone(),
+                            field,
+                            old,
+                            new,
+                            source,
+                        });
+                    }
                 }
             }
         }
     }
 
+    /// Write a field of the network called `network` from outside the simulation. The write
+    /// is logged when recording.
+    pub fn set_field(&mut self, network: &str, field: &str, value: YololValue) -> Option<()> {
+        self.collect_trace();
+        self.networks
+            .get_mut(network)?
+            .set_field(field, value.clone())?;
+        if let Some(recording) = &mut self.recording {
+            recording.inputs.push(Input {
+                tick: self.tick,
+                network: network.to_string(),
+                field: field.to_string(),
+                value,
+            });
+        }
+        Some(())
+    }
+
+    /// Log every following `set_field` until `stop_recording`.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording::default());
+    }
+
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+
+    /// Get the number of ticks run so far.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Get a reference to the network called `name`.
+    pub fn network(&self, name: &str) -> Option<&Network<R>> {
+        self.networks.get(name)
+    }
+
+    /// Get a mutable reference to the network called `name`.
+    pub fn network_mut(&mut self, name: &str) -> Option<&mut Network<R>> {
+        self.networks.get_mut(name)
+    }
+
+    pub fn networks(&self) -> impl Iterator<Item = (&String, &Network<R>)> {
+        self.networks.iter()
+    }
+
+    pub fn networks_mut(&mut self) -> impl Iterator<Item = (&String, &mut Network<R>)> {
+        self.networks.iter_mut()
+    }
+
     pub fn print_globals(&self) {
         for (name, network) in &self.networks {
             println!("Globals of network : {}", name);
@@ -79,27 +366,101 @@ impl<R: CodeRunner + Default> Networks<R> {
     }
 }
 
+type Change = (String, YololValue, YololValue, ChangeSource);
+
 #[derive(Debug)]
 pub struct Network<R: CodeRunner + Default> {
     devices: Vec<Device<R>>,
     globals: Vec<Field>,
+
+    trace: Option<Vec<Change>>,
+    lines: Vec<ChipOutcome>,
 }
 
 impl<R: CodeRunner + Default> Network<R> {
-    pub fn parse_all_chip_file(&mut self) {
+    pub fn parse_all_chip_file(&mut self, strict: bool) -> Vec<(String, ChipViolation)> {
+        let mut violations = vec![];
         for device in &mut self.devices {
             if let Device::Rack(rack) = device {
-                rack.parse_all_chip_file()
+                violations.append(&mut rack.parse_all_chip_file(strict));
             }
         }
+        violations
     }
 
-    pub fn step(&mut self) {
-        for device in &mut self.devices {
+    pub fn step(&mut self) -> Vec<ChipOutcome> {
+        let mut outcomes = vec![];
+        for (device, slot) in self.chip_slots() {
+            if let Some(outcome) = self.run_chip(device, slot) {
+                outcomes.push(ChipOutcome {
+                    device,
+                    slot,
+                    outcome,
+                });
+            }
+        }
+        outcomes
+    }
+
+    /// Run each chip on the latest globals and commit its writes before the next one runs.
+    pub fn step_interleaved(&mut self) -> Vec<ChipOutcome> {
+        let mut outcomes = vec![];
+        for (device, slot) in self.chip_slots() {
+            if let Some(outcome) = self.step_chip(device, slot) {
+                outcomes.push(ChipOutcome {
+                    device,
+                    slot,
+                    outcome,
+                });
+            }
+        }
+        self.commit();
+        outcomes
+    }
+
+    /// Every rack slot of the network, by device index and slot.
+    pub fn chip_slots(&self) -> Vec<(usize, usize)> {
+        let mut slots = vec![];
+        for (i, device) in self.devices.iter().enumerate() {
             if let Device::Rack(rack) = device {
-                rack.step();
+                slots.extend((0..rack.slots()).map(|slot| (i, slot)));
             }
         }
+        slots
+    }
+
+    /// Run one line of a chip on the globals it was last given, without writing its
+    /// globals back. Nothing happens if the rack is off.
+    pub fn run_chip(&mut self, device: usize, slot: usize) -> Option<StepOutcome> {
+        let rack = match self.devices.get_mut(device)? {
+            Device::Rack(rack) if rack.powered() => rack,
+            _ => return None,
+        };
+        let outcome = rack.chip_mut(slot)?.step()?;
+        if self.trace.is_some() {
+            self.lines.push(ChipOutcome {
+                device,
+                slot,
+                outcome: outcome.clone(),
+            });
+        }
+        Some(outcome)
+    }
+
+    /// Run one line of a chip on the latest globals and write its globals back. The devices
+    /// see the writes on the next `commit`.
+    pub fn step_chip(&mut self, device: usize, slot: usize) -> Option<StepOutcome> {
+        let rack = match self.devices.get_mut(device)? {
+            Device::Rack(rack) if rack.powered() => rack,
+            _ => return None,
+        };
+        let chip = rack.chip_mut(slot)?;
+        chip.update_globals(self.globals.clone());
+        let outcome = chip.step();
+        let changes = merge_globals(&mut self.globals, chip.get_global());
+        let line = outcome.as_ref().map_or(0, |o| o.line);
+        self.record(changes, ChangeSource::Chip { device, slot, line });
+        outcome
     }
 
     pub fn update_globals(&mut self) {
@@ -111,13 +472,87 @@ impl<R: CodeRunner + Default> Network<R> {
     }
 
     pub fn update(&mut self) {
-        let mut field = vec![];
+        let mut changes = vec![];
+        for (i, device) in self.devices.iter().enumerate() {
+            if let Device::Rack(rack) = device {
+                if !rack.powered() {
+                    continue;
+                }
+                for (slot, chip) in rack.chips().enumerate() {
+                    let line = self
+                        .lines
+                        .iter()
+                        .find(|o| o.device == i && o.slot == slot)
+                        .map_or(0, |o| o.outcome.line);
+                    let source = ChangeSource::Chip {
+                        device: i,
+                        slot,
+                        line,
+                    };
+                    for (field, old, new) in merge_globals(&mut self.globals, chip.get_global()) {
+                        changes.push((field, old, new, source.clone()));
+                    }
+                }
+            }
+        }
+        self.lines.clear();
+        if let Some(trace) = &mut self.trace {
+            trace.append(&mut changes);
+        }
+        self.commit();
+    }
+
+    fn record(&mut self, changes: Vec<(String, YololValue, YololValue)>, source: ChangeSource) {
+        if let Some(trace) = &mut self.trace {
+            for (field, old, new) in changes {
+                trace.push((field, old, new, source.clone()));
+            }
+        }
+    }
+
+    /// Write the globals to the devices and let the devices react to them.
+    pub fn commit(&mut self) {
+        self.write_devices();
         for device in &mut self.devices {
             if let Device::Rack(rack) = device {
-                field.append(&mut rack.get_global());
+                rack.update_state();
+            }
+        }
+        self.read_devices();
+    }
+
+    /// Copy the device fields into the network's globals.
+    fn read_devices(&mut self) {
+        for (i, device) in self.devices.iter().enumerate() {
+            for field in device.get_fields() {
+                match self.globals.iter_mut().find(|g| g.name() == field.name()) {
+                    Some(global) if **global != **field => {
+                        if let Some(trace) = &mut self.trace {
+                            trace.push((
+                                field.name().to_string(),
+                                (**global).clone(),
+                                (**field).clone(),
+                                ChangeSource::Device { device: i },
+                            ));
+                        }
+                        **global = (**field).clone();
+                    }
+                    Some(_) => (),
+                    None => self.globals.push(field.clone()),
+                }
+            }
+        }
+    }
+
+    /// Copy the network's globals into the device fields.
+    fn write_devices(&mut self) {
+        for global in &self.globals {
+            for device in &mut self.devices {
+                if let Some(value) = device.get_field_mut(global.name()) {
+                    *value = global.deref().clone();
+                }
             }
         }
-        self.set_globals(field);
     }
 
     pub fn print_globals(&self) {
@@ -126,23 +561,76 @@ impl<R: CodeRunner + Default> Network<R> {
         }
     }
 
+    /// Get a reference to the network's devices.
+    pub fn devices(&self) -> &[Device<R>] {
+        &self.devices
+    }
+
+    /// Get an environment holding the network's globals, to evaluate expressions on them.
+    pub fn environment(&self) -> Environment {
+        let mut env = Environment::default();
+        for field in &self.globals {
+            env.globals
+                .insert(field.name().to_string(), field.deref().clone());
+        }
+        env
+    }
+
     /// Get a reference to the network's globals.
     pub fn globals(&self) -> Vec<Field> {
         self.globals.clone()
     }
 
-    /// Set the network's globals.
-    pub fn set_globals(&mut self, globals: Vec<Field>) {
-        for field in globals {
-            let global = self
-                .globals
-                .iter_mut()
-                .find(|i| i.name().to_lowercase() == field.name().to_lowercase());
-            if let Some(global) = global {
-                **global = field.deref().clone();
+    /// Get the value of the global called `name`.
+    pub fn get_field(&self, name: &str) -> Option<&YololValue> {
+        let name = name.to_lowercase();
+        self.globals
+            .iter()
+            .find(|i| i.name() == name)
+            .map(|i| i.deref())
+    }
+
+    /// Write a field from outside the simulation, like a player pressing a button. The
+    /// global and every device field called `name` are updated. Outside of the crate, writes
+    /// go through `Networks::set_field` so they are recorded.
+    pub(crate) fn set_field(&mut self, name: &str, value: YololValue) -> Option<()> {
+        let name = name.to_lowercase();
+        let global = self.globals.iter_mut().find(|i| i.name() == name)?;
+        let old = std::mem::replace(&mut **global, value.clone());
+        if old != value {
+            self.record(
+                vec![(name.clone(), old, value.clone())],
+                ChangeSource::External,
+            );
+        }
+        for device in &mut self.devices {
+            if let Some(field) = device.get_field_mut(&name) {
+                *field = value.clone();
+            }
+        }
+        Some(())
+    }
+}
+
+/// Merge `fields` into `globals`, returning the name, old and new value of every global
+/// that changed.
+fn merge_globals(
+    globals: &mut [Field],
+    fields: Vec<Field>,
+) -> Vec<(String, YololValue, YololValue)> {
+    let mut changes = vec![];
+    for field in fields {
+        let global = globals
+            .iter_mut()
+            .find(|i| i.name().to_lowercase() == field.name().to_lowercase());
+        if let Some(global) = global {
+            if **global != *field {
+                let old = std::mem::replace(&mut **global, field.deref().clone());
+                changes.push((global.name().to_string(), old, field.deref().clone()));
             }
         }
     }
+    changes
 }
 
 impl<R: CodeRunner + Default> Network<R> {
@@ -159,9 +647,181 @@ impl<R: CodeRunner + Default> Network<R> {
                 }
             }
         }
-        Self {
+        let mut network = Self {
             devices,
             globals: vec![],
+            trace: None,
+            lines: vec![],
+        };
+        network.read_devices();
+        network
+    }
+}
+
+/// Increments the global `a` on every step.
+#[cfg(test)]
+#[derive(Default, Debug)]
+struct IncrementRunner(Vec<Field>);
+
+#[cfg(test)]
+impl CodeRunner for IncrementRunner {
+    fn parse(&mut self, _: &str) -> Option<()> {
+        Some(())
+    }
+
+    fn step(&mut self) -> StepOutcome {
+        for field in &mut self.0 {
+            **field = &**field + &1.into();
+        }
+        StepOutcome {
+            line: 1,
+            error: None,
+        }
+    }
+
+    fn update_globals(&mut self, globals: Vec<Field>) {
+        self.0 = globals.into_iter().filter(|f| f.name() == "a").collect();
+    }
+
+    fn get_global(&self) -> Vec<Field> {
+        self.0.clone()
+    }
+
+    fn clear_written(&mut self) {
+        self.0.clear();
+    }
+}
+
+#[cfg(test)]
+fn increment_network() -> Networks<IncrementRunner> {
+    test_network(&["increment", "increment"])
+}
+
+/// A directory for the files of a test, removed with its content when dropped.
+#[cfg(test)]
+pub(crate) struct TestDir(std::path::PathBuf);
+
+#[cfg(test)]
+impl TestDir {
+    pub(crate) fn new() -> Self {
+        use std::sync::atomic::AtomicUsize;
+        use std::sync::atomic::Ordering;
+
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "yolol-devices-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    pub(crate) fn path(&self) -> &std::path::Path {
+        &self.0
+    }
+
+    /// Write a file in the directory, returning its path.
+    pub(crate) fn write(&self, name: &str, content: &str) -> String {
+        let path = self.0.join(name);
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A network named `network` with a rack per script and a global `a`. The scripts are gone
+/// once loaded, their paths only naming the chips.
+#[cfg(test)]
+pub(crate) fn test_network<R: CodeRunner + Default>(scripts: &[&str]) -> Networks<R> {
+    use devices::chip::Chip;
+    use devices::chip::YololChip;
+    use devices::Rack;
+
+    let dir = TestDir::new();
+    let mut devices = vec![];
+    for (i, script) in scripts.iter().enumerate() {
+        let mut chip = YololChip::default();
+        chip.set_path(Some(dir.write(&format!("{}.yolol", i), script)));
+        let mut rack = Rack::default();
+        for (j, field) in rack.get_fields_mut().into_iter().enumerate() {
+            field.set_name(format!("rack{}field{}", i, j));
+        }
+        assert!(rack.insert_chip(0, Chip::Yolol(chip)).is_ok());
+        devices.push(Device::Rack(rack));
+    }
+    let mut global = Field::default();
+    global.set_name("a".to_string());
+    let mut network = Network {
+        devices,
+        globals: vec![global],
+        trace: None,
+        lines: vec![],
+    };
+    assert!(network.parse_all_chip_file(false).is_empty());
+    let mut networks = BTreeMap::new();
+    networks.insert("network".to_string(), network);
+    Networks {
+        networks,
+        relays: vec![],
+        strict: false,
+        tick_model: TickModel::default(),
+        tick: 0,
+        recording: None,
+        trace: None,
+    }
+}
+
+#[test]
+fn phased_tick_test() {
+    let mut networks = increment_network();
+    networks.step();
+    let report = networks.step();
+    let network = networks.network("network").unwrap();
+    assert_eq!(*network.globals()[0], 2.into());
+    assert_eq!(report["network"].len(), 2);
+    assert!(report["network"].iter().all(|chip| !chip.outcome.errored()));
+}
+
+#[test]
+fn interleaved_tick_test() {
+    let mut networks = increment_network();
+    networks.set_tick_model(TickModel::Interleaved);
+    networks.step();
+    networks.step();
+    let network = networks.network("network").unwrap();
+    assert_eq!(*network.globals()[0], 4.into());
+}
+
+#[test]
+fn waiting_chip_write_test() {
+    use devices::chip::Chip;
+    use yolol::YololRunner;
+
+    for tick_model in [TickModel::Phased, TickModel::Interleaved] {
+        let mut networks = test_network::<YololRunner>(&[":a = 7 goto 1", ":a = 100"]);
+        networks.set_tick_model(tick_model);
+        networks.step();
+        assert_eq!(
+            networks.network("network").unwrap().get_field("a"),
+            Some(&100.into())
+        );
+        let network = networks.networks.get_mut("network").unwrap();
+        if let Device::Rack(rack) = &mut network.devices[1] {
+            if let Some(Chip::Yolol(chip)) = rack.chip_mut(0) {
+                **chip.chip_wait_mut() = 5.into();
+            }
         }
+        networks.step();
+        assert_eq!(
+            networks.network("network").unwrap().get_field("a"),
+            Some(&7.into())
+        );
     }
 }
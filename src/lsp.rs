@@ -0,0 +1,659 @@
+//! A Language Server Protocol server for the YOLOL scripts of ship files. A script is checked
+//! against the networks of the chips running it, as found in the ships of the workspace.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde_json::json;
+use serde_json::Value;
+
+use crate::devices::chip::validate_source;
+use crate::devices::chip::Chip;
+use crate::devices::chip::ChipViolation;
+use crate::devices::chip::NoneRunner;
+use crate::devices::chip::CHIP_MAX_LINE_LENGTH;
+use crate::devices::Device;
+use crate::devices::DeviceTrait;
+use crate::protocol::same_file;
+use crate::yaml::YamlNode;
+use crate::yolol::globals;
+use crate::yolol::parse_line;
+use crate::Networks;
+
+const ERROR: u8 = 1;
+const WARNING: u8 = 2;
+
+/// A ship file, with the line ranges of its device entries.
+struct Ship {
+    path: PathBuf,
+    source: String,
+    networks: Networks<NoneRunner>,
+    /// The network and the first and last lines of each device, by network device index.
+    devices: BTreeMap<(String, usize), (usize, usize)>,
+    /// The scripts run by the chips of each network.
+    scripts: Vec<(PathBuf, String)>,
+}
+
+/// A device entry of a ship file, as found by [`device_entries`]. Lines are 0-based.
+#[derive(Debug, Clone, PartialEq)]
+struct DeviceEntry {
+    network: String,
+    tag: String,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Default)]
+pub struct LspServer {
+    root: Option<PathBuf>,
+    ships: Vec<Ship>,
+    /// The text of the open documents, by URI.
+    documents: BTreeMap<String, String>,
+    shutdown: bool,
+    exited: bool,
+}
+
+impl LspServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the client sent `exit`, and whether it sent `shutdown` first.
+    pub fn exited(&self) -> Option<bool> {
+        if self.exited {
+            Some(self.shutdown)
+        } else {
+            None
+        }
+    }
+
+    /// Handle a request or a notification, returning the response and the notifications it
+    /// caused.
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let mut notifications = vec![];
+        let result = match method {
+            "initialize" => {
+                self.initialize(params);
+                Ok(json!({
+                    "capabilities": {
+                        "textDocumentSync": 1,
+                        "hoverProvider": true,
+                        "definitionProvider": true,
+                    },
+                    "serverInfo": {"name": "yolol-lsp"},
+                }))
+            }
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "exit" => {
+                self.exited = true;
+                Ok(Value::Null)
+            }
+            "textDocument/didOpen" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.to_string(), text.to_string());
+                notifications.push(self.publish(uri));
+                Ok(Value::Null)
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|c| c.last()?["text"].as_str()) {
+                    self.documents.insert(uri.to_string(), text.to_string());
+                }
+                notifications.push(self.publish(uri));
+                Ok(Value::Null)
+            }
+            "textDocument/didSave" => {
+                let path = uri_to_path(params["textDocument"]["uri"].as_str().unwrap_or_default());
+                if let Some(i) = self.ships.iter().position(|s| same_file(&s.path, &path)) {
+                    match self.load_ship(&self.ships[i].path.clone()) {
+                        Some(ship) => self.ships[i] = ship,
+                        None => {
+                            self.ships.remove(i);
+                        }
+                    }
+                    let uris: Vec<String> = self.documents.keys().cloned().collect();
+                    for uri in uris {
+                        notifications.push(self.publish(&uri));
+                    }
+                }
+                Ok(Value::Null)
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.documents.remove(uri);
+                notifications.push(json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": {"uri": uri, "diagnostics": []},
+                }));
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => Ok(self.hover(params)),
+            "textDocument/definition" => Ok(self.definition(params)),
+            _ => Err(json!({"code": -32601, "message": format!("unsupported method {}", method)})),
+        };
+        let mut messages = vec![];
+        // Notifications have no id and get no response.
+        if !message["id"].is_null() {
+            let mut response = json!({"jsonrpc": "2.0", "id": message["id"]});
+            match result {
+                Ok(result) => response["result"] = result,
+                Err(error) => response["error"] = error,
+            }
+            messages.push(response);
+        }
+        messages.append(&mut notifications);
+        messages
+    }
+
+    /// Load the ships given as `initializationOptions.ships`, or every ship file at the root
+    /// of the workspace.
+    fn initialize(&mut self, params: &Value) {
+        self.root = params["rootUri"]
+            .as_str()
+            .map(uri_to_path)
+            .or_else(|| params["rootPath"].as_str().map(PathBuf::from));
+        let paths: Vec<PathBuf> = match params["initializationOptions"]["ships"].as_array() {
+            Some(ships) => ships
+                .iter()
+                .filter_map(|ship| ship.as_str())
+                .map(|ship| self.resolve(Path::new(ship), None))
+                .collect(),
+            None => self
+                .root
+                .as_ref()
+                .and_then(|root| std::fs::read_dir(root).ok())
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|path| {
+                    matches!(
+                        path.extension().and_then(|e| e.to_str()),
+                        Some("yaml" | "yml")
+                    )
+                })
+                .collect(),
+        };
+        self.ships = paths
+            .iter()
+            .filter_map(|path| self.load_ship(path))
+            .collect();
+    }
+
+    fn load_ship(&self, path: &Path) -> Option<Ship> {
+        let source = std::fs::read_to_string(path).ok()?;
+        let yaml = YamlNode::parse(&source).ok()?;
+        let networks = Networks::<NoneRunner>::deserialize(&yaml)?;
+        let entries = device_entries(&source);
+        let mut devices = BTreeMap::new();
+        let mut scripts = vec![];
+        for (name, network) in networks.networks() {
+            let entries: Vec<_> = entries
+                .iter()
+                .filter(|entry| entry.network == *name)
+                .collect();
+            let mut next = 0;
+            for (i, device) in network.devices().iter().enumerate() {
+                // Devices of unknown kinds are left out of the network, and some tags are
+                // aliases of the device name, so an entry is only used up when it matches.
+                let kind = device.get_device_name();
+                if let Some(j) = entries[next..].iter().position(|entry| entry.tag == kind) {
+                    let entry = entries[next + j];
+                    devices.insert((name.clone(), i), (entry.start, entry.end));
+                    next += j + 1;
+                }
+                if let Device::Rack(rack) = device {
+                    for chip in rack.chips() {
+                        if let Chip::Yolol(chip) = chip {
+                            if let Some(script) = chip.path() {
+                                let script = self.resolve(Path::new(script), Some(path));
+                                scripts.push((script, name.clone()));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Some(Ship {
+            path: path.to_path_buf(),
+            source,
+            networks,
+            devices,
+            scripts,
+        })
+    }
+
+    /// Scripts are looked up relative to the workspace root, as the simulator does from the
+    /// working directory, then relative to the ship.
+    fn resolve(&self, path: &Path, ship: Option<&Path>) -> PathBuf {
+        let mut candidates = vec![];
+        if let Some(root) = &self.root {
+            candidates.push(root.join(path));
+        }
+        if let Some(dir) = ship.and_then(|ship| ship.parent()) {
+            candidates.push(dir.join(path));
+        }
+        candidates
+            .into_iter()
+            .find(|candidate| candidate.exists())
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+
+    /// The ships and networks running a script.
+    fn networks(&self, uri: &str) -> Vec<(&Ship, &str)> {
+        let path = uri_to_path(uri);
+        let mut networks: Vec<(&Ship, &str)> = vec![];
+        for ship in &self.ships {
+            for (script, network) in &ship.scripts {
+                let known = networks
+                    .iter()
+                    .any(|(s, n)| std::ptr::eq(*s, ship) && *n == network);
+                if !known && same_file(script, &path) {
+                    networks.push((ship, network.as_str()));
+                }
+            }
+        }
+        networks
+    }
+
+    fn publish(&self, uri: &str) -> Value {
+        let source = self.documents.get(uri).map_or("", |s| s.as_str());
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": {"uri": uri, "diagnostics": self.diagnostics(uri, source)},
+        })
+    }
+
+    fn diagnostics(&self, uri: &str, source: &str) -> Vec<Value> {
+        let lines: Vec<&str> = source.lines().collect();
+        let mut diagnostics = vec![];
+        for violation in validate_source(source) {
+            let (line, start, end) = match violation {
+                ChipViolation::TooManyLines(_) => {
+                    (lines.len() - 1, 0, lines[lines.len() - 1].len())
+                }
+                ChipViolation::LineTooLong { line, length } => {
+                    (line - 1, CHIP_MAX_LINE_LENGTH, length)
+                }
+                ChipViolation::NonAscii { line, column, .. } => (line - 1, column - 1, column),
+                _ => continue,
+            };
+            diagnostics.push(diagnostic(line, start, end, WARNING, violation.to_string()));
+        }
+        let networks = self.networks(uri);
+        for (n, line) in lines.iter().enumerate() {
+            if let Err(error) = parse_line(line) {
+                let end = line.chars().count().max(error.column);
+                diagnostics.push(diagnostic(n, error.column - 1, end, ERROR, error.message));
+                continue;
+            }
+            for (column, name) in globals(line).unwrap_or_default() {
+                for (ship, network) in &networks {
+                    let fields = ship.networks.network(network).unwrap();
+                    if fields.get_field(&name).is_none() {
+                        diagnostics.push(diagnostic(
+                            n,
+                            column - 1,
+                            column + name.chars().count(),
+                            WARNING,
+                            format!("no device on network {} exposes :{}", network, name),
+                        ));
+                    }
+                }
+            }
+        }
+        diagnostics
+    }
+
+    /// The global under the cursor, if any.
+    fn global_at(&self, params: &Value) -> Option<(String, String)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let line = params["position"]["line"].as_u64()? as usize;
+        let character = params["position"]["character"].as_u64()? as usize;
+        let text = self.documents.get(uri)?.lines().nth(line)?;
+        globals(text)
+            .ok()?
+            .into_iter()
+            .find(|(column, name)| {
+                character + 1 >= *column && character < column + name.chars().count()
+            })
+            .map(|(_, name)| (uri.to_string(), name))
+    }
+
+    /// Every device defining a field, with the ship and network it is on.
+    fn definitions<'a>(&'a self, uri: &str, field: &str) -> Vec<(&'a Ship, &'a str, usize)> {
+        let mut definitions = vec![];
+        for (ship, network) in self.networks(uri) {
+            let devices = ship.networks.network(network).unwrap().devices();
+            for (i, device) in devices.iter().enumerate() {
+                if device.get_fields().iter().any(|f| f.name() == field) {
+                    definitions.push((ship, network, i));
+                }
+            }
+        }
+        definitions
+    }
+
+    fn hover(&self, params: &Value) -> Value {
+        let (uri, field) = match self.global_at(params) {
+            Some(global) => global,
+            None => return Value::Null,
+        };
+        let mut text = vec![];
+        for (ship, network, i) in self.definitions(&uri, &field) {
+            let device = &ship.networks.network(network).unwrap().devices()[i];
+            let value = device.get_field(&field).cloned().unwrap_or_default();
+            text.push(format!(
+                "`:{}` = {} on {} device {} ({})",
+                field,
+                value.to_literal(),
+                network,
+                i,
+                device.get_device_name()
+            ));
+            if let Some(line) = definition_line(ship, network, i, &field) {
+                let source = ship.source.lines().nth(line).unwrap_or_default();
+                text.push(format!("```yaml\n{}\n```", source.trim()));
+            }
+        }
+        if text.is_empty() {
+            return Value::Null;
+        }
+        json!({"contents": {"kind": "markdown", "value": text.join("\n\n")}})
+    }
+
+    fn definition(&self, params: &Value) -> Value {
+        let (uri, field) = match self.global_at(params) {
+            Some(global) => global,
+            None => return Value::Null,
+        };
+        let locations: Vec<Value> = self
+            .definitions(&uri, &field)
+            .into_iter()
+            .filter_map(|(ship, network, i)| {
+                let line = definition_line(ship, network, i, &field)?;
+                let length = ship.source.lines().nth(line)?.chars().count();
+                Some(json!({
+                    "uri": path_to_uri(&ship.path),
+                    "range": range(line, 0, length),
+                }))
+            })
+            .collect();
+        json!(locations)
+    }
+}
+
+/// The line of the entry of a device that names `field`, or the first line of the entry if
+/// the field keeps its default name.
+fn definition_line(ship: &Ship, network: &str, device: usize, field: &str) -> Option<usize> {
+    let (start, end) = *ship.devices.get(&(network.to_string(), device))?;
+    let named = ship
+        .source
+        .lines()
+        .enumerate()
+        .take(end + 1)
+        .skip(start)
+        .find(|(_, line)| contains_word(&line.to_lowercase(), field))
+        .map(|(n, _)| n);
+    Some(named.unwrap_or(start))
+}
+
+fn contains_word(text: &str, word: &str) -> bool {
+    let is_name = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(word)
+        .any(|(i, _)| !text[..i].ends_with(is_name) && !text[i + word.len()..].starts_with(is_name))
+}
+
+/// Find the device entries of a ship file, network by network. Only the block layout of
+/// YAML lists is understood, which is what ship files use.
+fn device_entries(source: &str) -> Vec<DeviceEntry> {
+    let mut entries: Vec<DeviceEntry> = vec![];
+    let mut network = String::new();
+    // The indentation of the `devices:` key and of its items.
+    let mut devices: Option<(usize, Option<usize>)> = None;
+    for (n, line) in source.lines().enumerate() {
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - content.len();
+        if let Some((key, item)) = devices {
+            if content.starts_with('-') && indent >= key && (item.is_none() || item == Some(indent))
+            {
+                devices = Some((key, Some(indent)));
+                let tag = content[1..].trim_start();
+                let tag = match tag.strip_prefix('!') {
+                    Some(tag) => tag
+                        .split(|c: char| c.is_whitespace() || c == '{')
+                        .next()
+                        .unwrap_or_default(),
+                    None => "",
+                };
+                entries.push(DeviceEntry {
+                    network: network.clone(),
+                    tag: tag.to_string(),
+                    start: n,
+                    end: n,
+                });
+                continue;
+            }
+            if item.is_some_and(|i| indent > i) {
+                if let Some(entry) = entries.last_mut() {
+                    entry.end = n;
+                }
+                continue;
+            }
+            devices = None;
+        }
+        let content = content.trim_start_matches('-').trim_start();
+        if let Some(name) = content.strip_prefix("name:") {
+            network = name
+                .trim()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_string();
+        } else if content.starts_with("devices:") {
+            devices = Some((indent, None));
+        }
+    }
+    entries
+}
+
+fn diagnostic(line: usize, start: usize, end: usize, severity: u8, message: String) -> Value {
+    json!({
+        "range": range(line, start, end),
+        "severity": severity,
+        "source": "yolol",
+        "message": message,
+    })
+}
+
+fn range(line: usize, start: usize, end: usize) -> Value {
+    json!({
+        "start": {"line": line, "character": start},
+        "end": {"line": line, "character": end},
+    })
+}
+
+/// Get the path of a `file://` URI, undoing its percent-encoding.
+fn uri_to_path(uri: &str) -> PathBuf {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    let mut bytes = vec![];
+    let mut chars = path.bytes();
+    while let Some(b) = chars.next() {
+        if b == b'%' {
+            let hex: Vec<u8> = chars.by_ref().take(2).collect();
+            if let Some(b) = std::str::from_utf8(&hex)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                bytes.push(b);
+                continue;
+            }
+            bytes.push(b'%');
+            bytes.extend(hex);
+        } else {
+            bytes.push(b);
+        }
+    }
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn path_to_uri(path: &Path) -> String {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let mut uri = "file://".to_string();
+    for b in path.to_string_lossy().bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            uri.push(b as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", b));
+        }
+    }
+    uri
+}
+
+#[test]
+fn device_entries_test() {
+    let entries = device_entries(
+        "networks:
+  - name: main
+    devices:
+      - !lamp
+        LampOn: {name: Light, value: 1}
+      - !rack
+        module: !chip_reader
+          slot1: !yolol_chip
+            script: door.yolol
+  - name: \"aux\"
+    devices:
+    - !button
+      ButtonState: Door
+",
+    );
+    let entry = |network: &str, tag: &str, start, end| DeviceEntry {
+        network: network.to_string(),
+        tag: tag.to_string(),
+        start,
+        end,
+    };
+    assert_eq!(
+        entries,
+        vec![
+            entry("main", "lamp", 3, 4),
+            entry("main", "rack", 5, 8),
+            entry("aux", "button", 11, 12),
+        ]
+    );
+}
+
+#[test]
+fn load_ship_test() {
+    let dir = crate::TestDir::new();
+    let ship = dir.write(
+        "ship.yaml",
+        "networks:
+  - name: main
+    devices:
+      - !relay
+        RailRelayIsEnabled: Relay
+      - !lamp
+        LampOn: Light
+",
+    );
+    let ship = LspServer::new().load_ship(Path::new(&ship)).unwrap();
+    assert_eq!(ship.devices.get(&("main".to_string(), 1)), Some(&(5, 6)));
+}
+
+#[test]
+fn session_test() {
+    let dir = crate::TestDir::new();
+    let source = format!(
+        ":light = 1\n:missing = 2\na = (\nb = \"{}\"",
+        "x".repeat(70)
+    );
+    let script = PathBuf::from(dir.write("door.yolol", &source));
+    dir.write(
+        "ship.yaml",
+        "networks:
+  - name: main
+    devices:
+      - !lamp
+        LampOn: {name: Light, value: 1}
+      - !rack
+        module: !chip_reader
+          slot1: !yolol_chip
+            script: door.yolol
+",
+    );
+    let uri = path_to_uri(&script);
+
+    let mut server = LspServer::new();
+    let messages = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {"rootUri": path_to_uri(dir.path())},
+    }));
+    assert_eq!(
+        messages[0]["result"]["capabilities"]["hoverProvider"],
+        json!(true)
+    );
+    let messages = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {"textDocument": {"uri": uri, "languageId": "yolol", "version": 1, "text": source}},
+    }));
+    assert_eq!(messages.len(), 1);
+    let diagnostics: Vec<(u64, String)> = messages[0]["params"]["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| {
+            (
+                d["range"]["start"]["line"].as_u64().unwrap(),
+                d["message"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(diagnostics.len(), 3);
+    assert!(diagnostics[0].0 == 3 && diagnostics[0].1.contains("70"));
+    assert_eq!(
+        diagnostics[1],
+        (1, "no device on network main exposes :missing".to_string())
+    );
+    assert_eq!(diagnostics[2].0, 2);
+
+    let position = json!({"textDocument": {"uri": uri}, "position": {"line": 0, "character": 3}});
+    let messages = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "textDocument/hover",
+        "params": position,
+    }));
+    let hover = messages[0]["result"]["contents"]["value"].as_str().unwrap();
+    assert!(hover.contains("`:light` = 1 on main device 0 (lamp)"));
+    assert!(hover.contains("LampOn: {name: Light, value: 1}"));
+    let messages = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "textDocument/definition",
+        "params": position,
+    }));
+    assert_eq!(
+        messages[0]["result"][0]["uri"],
+        json!(path_to_uri(&dir.path().join("ship.yaml")))
+    );
+    assert_eq!(messages[0]["result"][0]["range"]["start"]["line"], json!(4));
+
+    server.handle(&json!({"jsonrpc": "2.0", "id": 4, "method": "shutdown"}));
+    server.handle(&json!({"jsonrpc": "2.0", "method": "exit"}));
+    assert_eq!(server.exited(), Some(true));
+}
@@ -0,0 +1,76 @@
+//! Messages framed by a `Content-Length` header, as spoken by debug adapters and language
+//! servers, and the helpers both servers share.
+
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+use std::path::Path;
+
+use serde_json::Value;
+
+/// The largest message body `read_message` accepts, in bytes.
+pub const MAX_MESSAGE_LENGTH: usize = 64 << 20;
+
+/// Read a message framed by a `Content-Length` header. `None` at the end of the input.
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length =
+        length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no Content-Length"))?;
+    if length > MAX_MESSAGE_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Content-Length {} over {}", length, MAX_MESSAGE_LENGTH),
+        ));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn write_message<W: Write>(writer: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+/// Whether two paths name the same file. Paths that cannot be resolved are compared as
+/// they are.
+pub(crate) fn same_file(a: impl AsRef<Path>, b: impl AsRef<Path>) -> bool {
+    let (a, b) = (a.as_ref(), b.as_ref());
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[test]
+fn framing_test() {
+    let message = serde_json::json!({"seq": 1, "type": "event", "event": "initialized"});
+    let mut buffer = vec![];
+    write_message(&mut buffer, &message).unwrap();
+    let mut reader = io::Cursor::new(buffer);
+    assert_eq!(read_message(&mut reader).unwrap(), Some(message));
+    assert_eq!(read_message(&mut reader).unwrap(), None);
+}
+
+#[test]
+fn message_length_test() {
+    let header = format!("Content-Length: {}\r\n\r\n{{}}", MAX_MESSAGE_LENGTH + 1);
+    let error = read_message(&mut io::Cursor::new(header)).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,121 @@
+use crate::devices::chip::CodeRunner;
+use crate::value::YololValue;
+use crate::Networks;
+use crate::StepReport;
+
+/// A write made from outside the simulation before tick `tick` ran.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Input {
+    pub tick: u64,
+    pub network: String,
+    pub field: String,
+    pub value: YololValue,
+}
+
+/// The external writes of a run, in the order they were made.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Recording {
+    pub inputs: Vec<Input>,
+}
+
+/// Feeds a recording back into a simulation, tick by tick.
+#[derive(Debug)]
+pub struct Replay {
+    recording: Recording,
+    next: usize,
+    skipped: Vec<Input>,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            recording,
+            next: 0,
+            skipped: vec![],
+        }
+    }
+
+    /// Apply the inputs recorded for the current tick, then run it.
+    pub fn step<R: CodeRunner + Default>(&mut self, networks: &mut Networks<R>) -> StepReport {
+        while let Some(input) = self.recording.inputs.get(self.next) {
+            if input.tick > networks.tick() {
+                break;
+            }
+            if input.tick < networks.tick()
+                || networks
+                    .set_field(&input.network, &input.field, input.value.clone())
+                    .is_none()
+            {
+                self.skipped.push(input.clone());
+            }
+            self.next += 1;
+        }
+        networks.step()
+    }
+
+    /// Whether every input was replayed.
+    pub fn finished(&self) -> bool {
+        self.next >= self.recording.inputs.len()
+    }
+
+    /// Get the inputs that could not be replayed, because their network or field does not
+    /// exist in the simulation or their tick had already run.
+    pub fn skipped(&self) -> &[Input] {
+        &self.skipped
+    }
+}
+
+#[test]
+fn record_replay_test() {
+    let mut networks = crate::increment_network();
+    networks.start_recording();
+    networks.step();
+    networks.set_field("network", "A", 10.into()).unwrap();
+    networks.step();
+    networks.step();
+    networks.set_field("network", "a", (-5).into()).unwrap();
+    networks.step();
+    assert!(networks.set_field("network", "b", 0.into()).is_none());
+    let recording = networks.stop_recording().unwrap();
+    assert_eq!(recording.inputs.len(), 2);
+
+    let mut replayed = crate::increment_network();
+    let mut replay = Replay::new(recording);
+    for _ in 0..4 {
+        replay.step(&mut replayed);
+    }
+    assert!(replay.finished());
+    assert!(replay.skipped().is_empty());
+    assert_eq!(replayed.snapshot(), networks.snapshot());
+
+    let mut replay = Replay::new(Recording {
+        inputs: vec![Input {
+            tick: 0,
+            network: "network".to_string(),
+            field: "missing".to_string(),
+            value: 1.into(),
+        }],
+    });
+    replay.step(&mut crate::increment_network());
+    assert_eq!(replay.skipped()[0].field, "missing");
+
+    let mut late = crate::increment_network();
+    late.step();
+    let mut replay = Replay::new(Recording {
+        inputs: vec![Input {
+            tick: 0,
+            network: "network".to_string(),
+            field: "a".to_string(),
+            value: 10.into(),
+        }],
+    });
+    replay.step(&mut late);
+    assert!(replay.finished());
+    assert_eq!(replay.skipped()[0].tick, 0);
+    assert_eq!(
+        *late.network("network").unwrap().get_field("a").unwrap(),
+        2.into()
+    );
+}
@@ -0,0 +1,255 @@
+use std::fmt::Write;
+
+use crate::devices::chip::Chip;
+use crate::devices::chip::CodeRunner;
+use crate::devices::Device;
+use crate::devices::DeviceTrait;
+use crate::value::YololValue;
+use crate::yolol::parse_expr;
+use crate::yolol::parse_line;
+use crate::yolol::Environment;
+use crate::Network;
+use crate::Networks;
+
+pub const HELP: &str = "commands:
+  step [N]            run N ticks, 1 by default
+  get FIELD           print a field of the current network, like `get :LampOn`
+  set FIELD EXPR      write a field, like `set :ButtonState 1`
+  list networks       list the networks, the current one marked with *
+  list devices        list the devices of the current network by index
+  list fields         list the globals of the current network
+  show [KIND] N       show the fields and chips of device N, like `show rack 2`
+  use NETWORK         switch to another network
+  tick                print the current tick
+anything else is run as YOLOL against the globals of the current network: an expression
+is printed, and statements like `:Door = 1` write their globals back";
+
+/// An interactive console on a running simulation. Commands act on the current network,
+/// the first one at the start.
+#[derive(Debug, Default)]
+pub struct Repl {
+    network: String,
+}
+
+impl Repl {
+    pub fn new<R: CodeRunner + Default>(networks: &Networks<R>) -> Self {
+        Self {
+            network: networks
+                .networks()
+                .next()
+                .map(|(name, _)| name.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Get the name of the current network.
+    pub fn network(&self) -> &str {
+        &self.network
+    }
+
+    /// Run a command, returning what it prints.
+    pub fn execute<R: CodeRunner + Default>(
+        &mut self,
+        networks: &mut Networks<R>,
+        command: &str,
+    ) -> Result<String, String> {
+        let command = command.trim();
+        let mut words = command.split_whitespace();
+        let mut out = String::new();
+        match words.next().unwrap_or_default() {
+            "" => (),
+            "help" => out.push_str(HELP),
+            "tick" => out.push_str(&networks.tick().to_string()),
+            "step" => {
+                let ticks: u64 = match words.next() {
+                    Some(n) => n.parse().map_err(|_| format!("bad tick count {}", n))?,
+                    None => 1,
+                };
+                for _ in 0..ticks {
+                    let tick = networks.tick();
+                    for (name, outcomes) in networks.step() {
+                        for chip in outcomes {
+                            if let Some(error) = chip.outcome.error {
+                                writeln!(
+                                    out,
+                                    "tick {}: {} device {} slot {} line {}: {}",
+                                    tick, name, chip.device, chip.slot, chip.outcome.line, error
+                                )
+                                .unwrap();
+                            }
+                        }
+                    }
+                }
+                write!(out, "tick {}", networks.tick()).unwrap();
+            }
+            "get" => {
+                let field = field_name(words.next().ok_or("get needs a field")?);
+                let value = self
+                    .current(networks)?
+                    .get_field(&field)
+                    .ok_or(format!("no field :{}", field))?;
+                out.push_str(&value.to_literal());
+            }
+            "set" => {
+                let field = field_name(words.next().ok_or("set needs a field")?);
+                let expr = words.collect::<Vec<_>>().join(" ");
+                let value = self.eval(networks, &expr)?;
+                networks
+                    .set_field(&self.network, &field, value.clone())
+                    .ok_or(format!("no field :{}", field))?;
+                write!(out, ":{} = {}", field, value.to_literal()).unwrap();
+            }
+            "list" => match words.next() {
+                Some("networks") => {
+                    for (name, _) in networks.networks() {
+                        let mark = if *name == self.network { "*" } else { " " };
+                        writeln!(out, "{} {}", mark, name).unwrap();
+                    }
+                }
+                Some("devices") => {
+                    for (i, device) in self.current(networks)?.devices().iter().enumerate() {
+                        writeln!(out, "{} {}", i, device.get_device_name()).unwrap();
+                    }
+                }
+                Some("fields") => {
+                    for field in self.current(networks)?.globals() {
+                        writeln!(out, ":{} = {}", field.name(), field.to_literal()).unwrap();
+                    }
+                }
+                _ => return Err("list networks, devices or fields".to_string()),
+            },
+            "show" => {
+                let (kind, index) = match (words.next(), words.next()) {
+                    (Some(index), None) => (None, index),
+                    (Some(kind), Some(index)) => (Some(kind), index),
+                    _ => return Err("show needs a device index".to_string()),
+                };
+                let index: usize = index
+                    .parse()
+                    .map_err(|_| format!("bad device index {}", index))?;
+                let device = self
+                    .current(networks)?
+                    .devices()
+                    .get(index)
+                    .ok_or(format!("no device {}", index))?;
+                if let Some(kind) = kind {
+                    if kind != "device" && kind != device.get_device_name() {
+                        return Err(format!(
+                            "device {} is a {}",
+                            index,
+                            device.get_device_name()
+                        ));
+                    }
+                }
+                show_device(&mut out, index, device);
+            }
+            "use" => {
+                let name = words.next().ok_or("use needs a network")?;
+                networks
+                    .network(name)
+                    .ok_or(format!("no network {}", name))?;
+                self.network = name.to_string();
+            }
+            _ => return self.run_yolol(networks, command),
+        }
+        Ok(out.trim_end().to_string())
+    }
+
+    fn current<'a, R: CodeRunner + Default>(
+        &self,
+        networks: &'a Networks<R>,
+    ) -> Result<&'a Network<R>, String> {
+        networks
+            .network(&self.network)
+            .ok_or(format!("no network {}", self.network))
+    }
+
+    fn env<R: CodeRunner + Default>(&self, networks: &Networks<R>) -> Result<Environment, String> {
+        Ok(self.current(networks)?.environment())
+    }
+
+    fn eval<R: CodeRunner + Default>(
+        &self,
+        networks: &Networks<R>,
+        expr: &str,
+    ) -> Result<YololValue, String> {
+        let expr = parse_expr(expr).map_err(|e| e.to_string())?;
+        self.env(networks)?.eval(&expr).map_err(|e| e.to_string())
+    }
+
+    /// Print an expression, or run statements and write the globals they set.
+    fn run_yolol<R: CodeRunner + Default>(
+        &self,
+        networks: &mut Networks<R>,
+        source: &str,
+    ) -> Result<String, String> {
+        if let Ok(expr) = parse_expr(source) {
+            let value = self.env(networks)?.eval(&expr).map_err(|e| e.to_string())?;
+            return Ok(value.to_literal());
+        }
+        let line = parse_line(source).map_err(|e| e.to_string())?;
+        let mut env = self.env(networks)?;
+        env.exec(&line.statements).map_err(|e| e.to_string())?;
+        let mut out = String::new();
+        for name in env.written() {
+            let value = env.globals[name].clone();
+            writeln!(out, ":{} = {}", name, value.to_literal()).unwrap();
+            if networks.set_field(&self.network, name, value).is_none() {
+                writeln!(out, "no field :{}, not written", name).unwrap();
+            }
+        }
+        Ok(out.trim_end().to_string())
+    }
+}
+
+fn field_name(field: &str) -> String {
+    field.trim_start_matches(':').to_lowercase()
+}
+
+fn show_device<R: CodeRunner + Default>(out: &mut String, index: usize, device: &Device<R>) {
+    writeln!(out, "{} {}", index, device.get_device_name()).unwrap();
+    for field in device.get_fields() {
+        writeln!(out, "  :{} = {}", field.name(), field.to_literal()).unwrap();
+    }
+    if let Device::Rack(rack) = device {
+        let power = if rack.powered() { "on" } else { "off" };
+        writeln!(out, "  module {} ({})", rack.module_kind().name, power).unwrap();
+        for (slot, chip) in rack.chips().enumerate() {
+            match chip {
+                Chip::None => writeln!(out, "  slot {}: empty", slot),
+                Chip::Memory(_) => writeln!(out, "  slot {}: memory chip", slot),
+                Chip::Yolol(chip) => {
+                    let path = chip.path().unwrap_or("no script");
+                    match chip.runner() {
+                        Some(runner) => writeln!(
+                            out,
+                            "  slot {}: yolol chip {} at line {}, chip wait {}",
+                            slot,
+                            path,
+                            runner.save_state().line,
+                            chip.chip_wait().to_literal()
+                        ),
+                        None => writeln!(out, "  slot {}: yolol chip {} (not loaded)", slot, path),
+                    }
+                }
+            }
+            .unwrap();
+        }
+    }
+}
+
+#[test]
+fn execute_test() {
+    let mut networks = crate::increment_network();
+    let mut repl = Repl::new(&networks);
+    let mut run = |command: &str| repl.execute(&mut networks, command);
+    assert_eq!(run("step 3").unwrap(), "tick 3");
+    assert_eq!(run("get :A").unwrap(), "3");
+    assert_eq!(run("set :a 2 * 5").unwrap(), ":a = 10");
+    assert_eq!(run(":a + 1").unwrap(), "11");
+    assert_eq!(run(":a = \"x\"").unwrap(), ":a = \"x\"");
+    assert_eq!(run("list devices").unwrap(), "0 rack\n1 rack");
+    assert!(run("show rack 1").unwrap().contains("1.yolol at line"));
+    assert!(run("show lamp 1").is_err());
+    assert!(run("get :missing").is_err());
+}
@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+
+use crate::devices::chip::Chip;
+use crate::devices::chip::CodeRunner;
+use crate::devices::chip::RunnerState;
+use crate::devices::Device;
+use crate::devices::DeviceTrait;
+use crate::field::Field;
+use crate::value::YololValue;
+use crate::Networks;
+
+/// The state of a simulation at a given tick. A snapshot only holds state, so it must be
+/// restored into `Networks` loaded from the same ship file.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot {
+    pub tick: u64,
+    pub networks: BTreeMap<String, NetworkSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NetworkSnapshot {
+    pub globals: Vec<Field>,
+    pub devices: Vec<DeviceSnapshot>,
+}
+
+/// The fields of a device, including the power state of racks, and the chips of racks by
+/// slot.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceSnapshot {
+    pub fields: Vec<Field>,
+    pub chips: Vec<Option<ChipSnapshot>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChipSnapshot {
+    pub chip_wait: YololValue,
+    pub runner: Option<RunnerState>,
+}
+
+impl<R: CodeRunner + Default> Networks<R> {
+    pub fn snapshot(&self) -> Snapshot {
+        let mut networks = BTreeMap::new();
+        for (name, network) in &self.networks {
+            let devices = network
+                .devices()
+                .iter()
+                .map(|device| DeviceSnapshot {
+                    fields: device.get_fields().into_iter().cloned().collect(),
+                    chips: match device {
+                        Device::Rack(rack) => rack.chips().map(snapshot_chip).collect(),
+                        _ => vec![],
+                    },
+                })
+                .collect();
+            networks.insert(
+                name.clone(),
+                NetworkSnapshot {
+                    globals: network.globals(),
+                    devices,
+                },
+            );
+        }
+        Snapshot {
+            tick: self.tick,
+            networks,
+        }
+    }
+
+    /// Bring the simulation back to `snapshot`. Networks of the snapshot that are not loaded
+    /// are skipped. The restore is not a field change, so nothing is traced, and the trace
+    /// not yet taken is dropped along with the rest of the abandoned state.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.tick = snapshot.tick;
+        if let Some(trace) = &mut self.trace {
+            trace.clear();
+        }
+        for (name, network_snapshot) in &snapshot.networks {
+            let network = match self.networks.get_mut(name) {
+                Some(network) => network,
+                None => continue,
+            };
+            network.globals = network_snapshot.globals.clone();
+            network.lines.clear();
+            if let Some(trace) = &mut network.trace {
+                trace.clear();
+            }
+            for (device, device_snapshot) in
+                network.devices.iter_mut().zip(&network_snapshot.devices)
+            {
+                for (field, snapshot) in device
+                    .get_fields_mut()
+                    .into_iter()
+                    .zip(&device_snapshot.fields)
+                {
+                    **field = (**snapshot).clone();
+                }
+                if let Device::Rack(rack) = device {
+                    for (chip, chip_snapshot) in rack.chips_mut().zip(&device_snapshot.chips) {
+                        if let (Chip::Yolol(chip), Some(chip_snapshot)) = (chip, chip_snapshot) {
+                            **chip.chip_wait_mut() = chip_snapshot.chip_wait.clone();
+                            if let (Some(runner), Some(state)) =
+                                (chip.runner_mut(), &chip_snapshot.runner)
+                            {
+                                runner.load_state(state);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn snapshot_chip<R: CodeRunner + Default>(chip: &Chip<R>) -> Option<ChipSnapshot> {
+    match chip {
+        Chip::Yolol(chip) => Some(ChipSnapshot {
+            chip_wait: (**chip.chip_wait()).clone(),
+            runner: chip.runner().map(|runner| runner.save_state()),
+        }),
+        _ => None,
+    }
+}
+
+#[test]
+fn snapshot_test() {
+    let mut networks = crate::increment_network();
+    networks.step();
+    let snapshot = networks.snapshot();
+    networks.step();
+    networks.step();
+    assert_eq!(*networks.network("network").unwrap().globals()[0], 3.into());
+    networks.restore(&snapshot);
+    assert_eq!(networks.tick(), 1);
+    assert_eq!(*networks.network("network").unwrap().globals()[0], 1.into());
+    networks.step();
+    assert_eq!(networks.snapshot().networks, {
+        let mut networks = crate::increment_network();
+        networks.step();
+        networks.step();
+        networks.snapshot().networks
+    });
+}
+
+#[test]
+fn restore_trace_test() {
+    let mut networks = crate::increment_network();
+    networks.set_tracing(true);
+    networks.step();
+    let snapshot = networks.snapshot();
+    networks.step();
+    networks.begin_tick();
+    let chip = crate::ChipId {
+        network: "network".to_string(),
+        device: 0,
+        slot: 0,
+    };
+    networks.step_chip(&chip).unwrap();
+    networks.restore(&snapshot);
+    assert!(networks.take_trace().is_empty());
+    assert!(networks.network("network").unwrap().lines.is_empty());
+    networks.step();
+    assert!(!networks.take_trace().is_empty());
+}
@@ -0,0 +1,131 @@
+#[cfg(all(feature = "serde", feature = "serde_json"))]
+use std::io;
+#[cfg(all(feature = "serde", feature = "serde_json"))]
+use std::io::Write;
+
+use crate::value::YololValue;
+
+/// What made a field change. Serialized as an object whose `type` names the variant, like
+/// `{"type":"device","device":2}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "lowercase"))]
+pub enum ChangeSource {
+    /// A chip, `device` being the index of its rack in the network's devices and `line`
+    /// the 1-based line it ran this tick.
+    Chip {
+        device: usize,
+        slot: usize,
+        line: usize,
+    },
+    /// A device reacting to its fields, like a rack toggled by its button.
+    Device { device: usize },
+    /// A relay copying the globals of the network `network`.
+    Relay { network: String },
+    /// A write from outside the simulation.
+    External,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldChange {
+    pub tick: u64,
+    pub network: String,
+    pub field: String,
+    pub old: YololValue,
+    pub new: YololValue,
+    pub source: ChangeSource,
+}
+
+/// Write the changes as JSON lines, in their serde representation.
+#[cfg(all(feature = "serde", feature = "serde_json"))]
+pub fn write_jsonl<W: Write>(changes: &[FieldChange], mut writer: W) -> io::Result<()> {
+    for change in changes {
+        serde_json::to_writer(&mut writer, change)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "serde", feature = "serde_json"))]
+#[test]
+fn write_jsonl_test() {
+    let change = FieldChange {
+        tick: 3,
+        network: "main".to_string(),
+        field: "door".to_string(),
+        old: 0.into(),
+        new: "a \"b\"".into(),
+        source: ChangeSource::Chip {
+            device: 1,
+            slot: 0,
+            line: 4,
+        },
+    };
+    let mut out = vec![];
+    write_jsonl(&[change.clone(), change], &mut out).unwrap();
+    let line = r#"{"tick":3,"network":"main","field":"door","old":{"int":"0"},"new":{"string":"a \"b\""},"source":{"type":"chip","device":1,"slot":0,"line":4}}"#;
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        format!("{}\n{}\n", line, line)
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_test() {
+    let sources = vec![
+        ChangeSource::Chip {
+            device: 1,
+            slot: 0,
+            line: 4,
+        },
+        ChangeSource::Device { device: 2 },
+        ChangeSource::Relay {
+            network: "aux".to_string(),
+        },
+        ChangeSource::External,
+    ];
+    for source in sources {
+        let change = FieldChange {
+            tick: 3,
+            network: "main".to_string(),
+            field: "door".to_string(),
+            old: 1.5.into(),
+            new: "open".into(),
+            source,
+        };
+        let json = serde_json::to_string(&change).unwrap();
+        assert_eq!(serde_json::from_str::<FieldChange>(&json).unwrap(), change);
+    }
+}
+
+#[test]
+fn trace_test() {
+    let mut networks = crate::increment_network();
+    networks.set_tracing(true);
+    networks.step();
+    networks.set_field("network", "a", 5.into()).unwrap();
+    networks.step();
+    let trace: Vec<_> = networks
+        .take_trace()
+        .into_iter()
+        .filter(|change| change.field == "a")
+        .collect();
+    assert_eq!(trace.len(), 3);
+    assert_eq!(trace[0].tick, 0);
+    assert_eq!(trace[0].new, 1.into());
+    assert_eq!(trace[1].tick, 1);
+    assert_eq!(trace[1].source, ChangeSource::External);
+    assert_eq!(trace[2].old, 5.into());
+    assert_eq!(trace[2].new, 6.into());
+    assert_eq!(
+        trace[2].source,
+        ChangeSource::Chip {
+            device: 0,
+            slot: 0,
+            line: 1
+        }
+    );
+    assert!(networks.take_trace().is_empty());
+}
@@ -0,0 +1,23 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum YololError {
+    DivideByZero,
+    StringOperation { op: &'static str },
+    EmptyStringDecrement,
+}
+
+impl Display for YololError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            YololError::DivideByZero => write!(f, "division by zero"),
+            YololError::StringOperation { op } => {
+                write!(f, "operator {} is not defined on strings", op)
+            }
+            YololError::EmptyStringDecrement => write!(f, "cannot decrement an empty string"),
+        }
+    }
+}
+
+impl std::error::Error for YololError {}
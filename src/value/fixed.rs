@@ -0,0 +1,293 @@
+//! Integer helpers for the fixed-point arithmetic of `YololInt`. Nothing here goes through
+//! floating point, so results do not depend on the platform's `f64`.
+
+use std::convert::TryFrom;
+
+/// Scale of the high precision values used for the trigonometry.
+const PRECISION: i128 = 1_000_000_000_000_000_000;
+
+/// Pi scaled by `PRECISION`.
+const PI: i128 = 3_141_592_653_589_793_238;
+
+/// `sin(d)` for every whole degree `d` from 0 to 90, scaled by `PRECISION` and rounded.
+#[rustfmt::skip]
+const SIN_TABLE: [i128; 91] = [
+    0, 17452406437283513, 34899496702500972, 52335956242943833,
+    69756473744125301, 87155742747658174, 104528463267653471, 121869343405147481,
+    139173100960065444, 156434465040230869, 173648177666930349, 190808995376544812,
+    207911690817759337, 224951054343864998, 241921895599667723, 258819045102520762,
+    275637355816999186, 292371704722736728, 309016994374947424, 325568154457156669,
+    342020143325668733, 358367949545300273, 374606593415912035, 390731128489273755,
+    406736643075800208, 422618261740699436, 438371146789077417, 453990499739546792,
+    469471562785890776, 484809620246337029, 500000000000000000, 515038074910054210,
+    529919264233204954, 544639035015027082, 559192903470746830, 573576436351046096,
+    587785252292473129, 601815023152048280, 615661475325658280, 629320391049837453,
+    642787609686539326, 656059028990507285, 669130606358858214, 681998360062498500,
+    694658370458997287, 707106781186547524, 719339800338651139, 731353701619170483,
+    743144825477394235, 754709580222771998, 766044443118978035, 777145961456970880,
+    788010753606721957, 798635510047292846, 809016994374947424, 819152044288991790,
+    829037572555041692, 838670567945424030, 848048096156425970, 857167300702112287,
+    866025403784438647, 874619707139395800, 882947592858926942, 891006524188367862,
+    898794046299166993, 906307787036649963, 913545457642600896, 920504853452440327,
+    927183854566787401, 933580426497201749, 939692620785908384, 945518575599316810,
+    951056516295153572, 956304755963035481, 961261695938318862, 965925826289068287,
+    970295726275996472, 974370064785235229, 978147600733805638, 981627183447663953,
+    984807753012208059, 987688340595137726, 990268068741570315, 992546151641322035,
+    994521895368273337, 996194698091745532, 997564050259824248, 998629534754573874,
+    999390827019095730, 999847695156391239, 1000000000000000000,
+];
+
+/// Divide rounding half away from zero.
+pub(super) fn div_round(n: i128, d: i128) -> i128 {
+    let q = n / d;
+    let r = n % d;
+    if 2 * r.abs() >= d.abs() {
+        q + (n.signum() * d.signum())
+    } else {
+        q
+    }
+}
+
+/// Integer square root, rounded to the nearest integer.
+pub(super) fn sqrt_round(n: i128) -> i128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = (x + 1) / 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    if n - x * x > x {
+        x + 1
+    } else {
+        x
+    }
+}
+
+/// Sine and cosine of an angle below one degree given in thousandths of a degree, scaled by
+/// `PRECISION`.
+fn small_sin_cos(millidegrees: i128) -> (i128, i128) {
+    let t = millidegrees * PI / 180_000;
+    let mut sin = 0;
+    let mut cos = 0;
+    let mut term = PRECISION;
+    let mut n = 0;
+    while term != 0 {
+        if n % 2 == 0 {
+            cos += term;
+        } else {
+            sin += term;
+        }
+        n += 1;
+        term = term * t / PRECISION / n;
+        if n % 4 == 2 || n % 4 == 3 {
+            term = -term.abs();
+        } else {
+            term = term.abs();
+        }
+    }
+    (sin, cos)
+}
+
+/// Sine of an angle given in thousandths of a degree, scaled by `PRECISION`.
+pub(super) fn sin(millidegrees: i64) -> i128 {
+    let x = millidegrees.rem_euclid(360_000) as i128;
+    let (x, sign) = if x < 180_000 {
+        (x, 1)
+    } else {
+        (x - 180_000, -1)
+    };
+    let x = if x > 90_000 { 180_000 - x } else { x };
+    let degrees = (x / 1000) as usize;
+    let (sin_f, cos_f) = small_sin_cos(x % 1000);
+    let sin_d = SIN_TABLE[degrees];
+    let cos_d = SIN_TABLE[90 - degrees];
+    sign * (sin_d * cos_f + cos_d * sin_f) / PRECISION
+}
+
+/// Cosine of an angle given in thousandths of a degree, scaled by `PRECISION`.
+pub(super) fn cos(millidegrees: i64) -> i128 {
+    sin((millidegrees.rem_euclid(360_000)) + 90_000)
+}
+
+/// Convert a value scaled by `PRECISION` to a raw `YololInt` value.
+pub(super) fn to_raw(v: i128) -> i64 {
+    div_round(v, PRECISION / 1000) as i64
+}
+
+/// The natural logarithm of 2, scaled by `PRECISION`.
+const LN_2: i128 = 693_147_180_559_945_309;
+
+/// Convert a raw `YololInt` value to a value scaled by `PRECISION`.
+fn from_raw(raw: i64) -> i128 {
+    raw as i128 * (PRECISION / 1000)
+}
+
+/// Convert a value scaled by `PRECISION` to a raw `YololInt` value, `None` if it does not
+/// fit.
+fn checked_to_raw(v: i128) -> Option<i64> {
+    i64::try_from(div_round(v, PRECISION / 1000)).ok()
+}
+
+/// Multiply two values scaled by `PRECISION`, `None` on overflow.
+fn mul(a: i128, b: i128) -> Option<i128> {
+    let (a1, a0) = (a / PRECISION, a % PRECISION);
+    let (b1, b0) = (b / PRECISION, b % PRECISION);
+    a1.checked_mul(b1)?
+        .checked_mul(PRECISION)?
+        .checked_add(a1.checked_mul(b0)?)?
+        .checked_add(a0.checked_mul(b1)?)?
+        .checked_add(a0 * b0 / PRECISION)
+}
+
+/// Sum the series `x - x^3/3 + x^5/5 - ...`, or every term positive when `alternate` is
+/// false. `x` is scaled by `PRECISION` and must be well below 1.
+fn odd_series(x: i128, alternate: bool) -> i128 {
+    let x2 = x * x / PRECISION;
+    let mut sum = 0;
+    let mut term = x;
+    let mut n = 0;
+    while term != 0 {
+        sum += term / (2 * n + 1);
+        term = term * x2 / PRECISION;
+        if alternate {
+            term = -term;
+        }
+        n += 1;
+    }
+    sum
+}
+
+/// Arctangent in radians, both scaled by `PRECISION`.
+fn atan(x: i128) -> i128 {
+    if x < 0 {
+        return -atan(-x);
+    }
+    if x > PRECISION {
+        return PI / 2 - atan(PRECISION * PRECISION / x);
+    }
+    // Two halvings of the angle bring it under 0.2 so the series converges quickly.
+    let mut x = x;
+    for _ in 0..2 {
+        x = x * PRECISION / (PRECISION + sqrt_round(PRECISION * PRECISION + x * x));
+    }
+    4 * odd_series(x, true)
+}
+
+/// Convert radians scaled by `PRECISION` to a raw `YololInt` value in degrees.
+fn to_raw_degrees(radians: i128) -> i64 {
+    div_round(radians * 180_000, PI) as i64
+}
+
+/// Arctangent in degrees of a raw `YololInt` value.
+pub(super) fn atan_degrees(raw: i64) -> i64 {
+    to_raw_degrees(atan(from_raw(raw)))
+}
+
+/// Arcsine in degrees of a raw `YololInt` value, `None` outside of -1 to 1.
+pub(super) fn asin_degrees(raw: i64) -> Option<i64> {
+    if !(-1000..=1000).contains(&raw) {
+        return None;
+    }
+    let x = from_raw(raw);
+    if x.abs() == PRECISION {
+        return Some(to_raw_degrees(x.signum() * PI / 2));
+    }
+    let cos = sqrt_round(PRECISION * PRECISION - x * x);
+    Some(to_raw_degrees(atan(x * PRECISION / cos)))
+}
+
+/// Arccosine in degrees of a raw `YololInt` value, `None` outside of -1 to 1.
+pub(super) fn acos_degrees(raw: i64) -> Option<i64> {
+    asin_degrees(raw).map(|asin| 90_000 - asin)
+}
+
+/// Natural logarithm of a positive value, both scaled by `PRECISION`.
+fn ln(x: i128) -> i128 {
+    let mut m = x;
+    let mut k = 0;
+    while m >= 2 * PRECISION {
+        m /= 2;
+        k += 1;
+    }
+    while m < PRECISION {
+        m *= 2;
+        k -= 1;
+    }
+    let z = (m - PRECISION) * PRECISION / (m + PRECISION);
+    k * LN_2 + 2 * odd_series(z, false)
+}
+
+/// Exponential of a value scaled by `PRECISION`, `None` on overflow.
+fn exp(t: i128) -> Option<i128> {
+    let k = div_round(t, LN_2);
+    let r = t - k * LN_2;
+    let mut sum = 0;
+    let mut term = PRECISION;
+    let mut n = 0;
+    while term != 0 {
+        sum += term;
+        n += 1;
+        term = term * r / PRECISION / n;
+    }
+    if k >= 0 {
+        sum.checked_mul(1i128.checked_shl(u32::try_from(k).ok()?)?)
+    } else if k > -120 {
+        Some(div_round(sum, 1 << -k))
+    } else {
+        Some(0)
+    }
+}
+
+/// `base` to the power of a positive whole `n`, scaled by `PRECISION`, `None` on overflow.
+fn powi(mut base: i128, mut n: i64) -> Option<i128> {
+    let mut r = PRECISION;
+    loop {
+        if n % 2 == 1 {
+            r = mul(r, base)?;
+        }
+        n /= 2;
+        if n == 0 {
+            return Some(r);
+        }
+        base = mul(base, base)?;
+    }
+}
+
+/// `base` to the power of `exponent`, both raw `YololInt` values. `None` when the result is
+/// undefined or does not fit.
+pub(super) fn pow(base: i64, exponent: i64) -> Option<i64> {
+    let x = from_raw(base);
+    if exponent == 0 {
+        return Some(1000);
+    }
+    if base == 0 {
+        return if exponent > 0 { Some(0) } else { None };
+    }
+    let r = if exponent % 1000 == 0 {
+        let n = exponent / 1000;
+        match powi(x, n.unsigned_abs() as i64) {
+            Some(r) if n > 0 => r,
+            Some(0) => return None,
+            Some(r) => div_round(PRECISION * PRECISION, r),
+            // The power is too large to fit, so its inverse rounds to nothing.
+            None if n < 0 => 0,
+            None => return None,
+        }
+    } else {
+        if base < 0 {
+            return None;
+        }
+        let ln = ln(x);
+        match mul(from_raw(exponent), ln) {
+            Some(t) => exp(t)?,
+            // The logarithm is never 0 here, and a hugely negative exponent of the
+            // exponential rounds to nothing.
+            None if (exponent < 0) != (ln < 0) => 0,
+            None => return None,
+        }
+    };
+    checked_to_raw(r)
+}
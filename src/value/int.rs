@@ -1,5 +1,7 @@
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::hash::Hash;
+use std::hash::Hasher;
 use std::ops::Add;
 use std::ops::Div;
 use std::ops::Mul;
@@ -7,13 +9,25 @@ use std::ops::Not;
 use std::ops::Rem;
 use std::ops::Sub;
 
+use super::fixed;
+use super::overflow;
+use super::overflow::Overflow;
 use super::ValueTrait;
+use super::YololError;
 use super::YololValue;
 
 #[derive(Clone, Copy, Debug, Default)]
 pub struct YololInt(i64);
 
 impl YololInt {
+    /// Number of raw units in one.
+    pub const SCALE: i64 = 1000;
+    pub const MAX: YololInt = YololInt(i64::MAX);
+    /// Also the result of operations the game cannot compute, like `sqrt -1`.
+    pub const MIN: YololInt = YololInt(i64::MIN);
+    /// What the game returns for `tan 90`.
+    pub const TAN_ASYMPTOTE: YololInt = YololInt(-22_877_332_428);
+
     pub fn new_raw(v: i64) -> Self {
         Self(v)
     }
@@ -25,118 +39,112 @@ impl PartialEq for YololInt {
     }
 }
 
+impl Eq for YololInt {}
+
 impl PartialOrd for YololInt {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.0.partial_cmp(&other.0)
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for YololInt {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Hash for YololInt {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
     }
 }
 
 impl ValueTrait for YololInt {
     fn post_inc(&mut self) -> YololValue {
+        let o = *self;
         *self = &*self + &1.into();
-        (*self).into()
+        o.into()
     }
     fn pre_inc(&mut self) -> YololValue {
-        let o = *self;
         *self = &*self + &1.into();
-        o.into()
+        (*self).into()
     }
 
-    fn post_dec(&mut self) -> Option<YololValue> {
+    fn post_dec(&mut self) -> Result<YololValue, YololError> {
+        let o = *self;
         *self = &*self - &1.into();
-        Some((*self).into())
+        Ok(o.into())
     }
 
-    fn pre_dec(&mut self) -> Option<YololValue> {
-        let o = *self;
+    fn pre_dec(&mut self) -> Result<YololValue, YololError> {
         *self = &*self - &1.into();
-        Some(o.into())
+        Ok((*self).into())
     }
 
-    fn fac(&self) -> Option<YololValue> {
-        let b = self.into();
+    fn fac(&self) -> Result<YololValue, YololError> {
+        let b: i64 = self.into();
         let mut product: i64 = 1;
         if b < 0 {
-            return Some(Self(-9223372036854775808).into());
+            return Ok(Self::MIN.into());
         } else if b > 1 {
             for i in 1..=b {
-                product = product.wrapping_mul(i);
+                product = overflow::FACTORIAL.apply(product as i128 * i as i128);
             }
         } else {
-            return Some(1.into());
+            return Ok(1.into());
         }
-        Some(product.into())
+        Ok(Self(overflow::FACTORIAL.apply(product as i128 * Self::SCALE as i128)).into())
     }
 
-    fn abs(&self) -> Option<YololValue> {
-        if self.0 == -9223372036854775808 {
-            return Some(Self(-9223372036854775808).into());
+    fn abs(&self) -> Result<YololValue, YololError> {
+        if self == &Self::MIN {
+            return Ok(Self::MIN.into());
         }
-        let f: f64 = self.into();
-        Some(f.abs().into())
+        Ok(Self(self.0.abs()).into())
     }
 
-    fn sqrt(&self) -> Option<YololValue> {
+    fn sqrt(&self) -> Result<YololValue, YololError> {
         let f = self.0;
-        if !(0..9223372036854775000).contains(&f) {
-            return Some(Self(-9223372036854775808).into());
+        if !(0..overflow::SQRT_LIMIT).contains(&f) {
+            return Ok(Self::MIN.into());
         }
-        let v = (f as f64).sqrt() / 31.6227766017;
-        let k = ((v % 0.001) * 10000.).round() as i64 / 10;
-        Some(Self((v * 1000.).trunc() as i64 + k).into())
+        Ok(Self(fixed::sqrt_round(f as i128 * 1000) as i64).into())
     }
 
-    fn sin(&self) -> Option<YololValue> {
-        let f: f64 = self.into();
-        Some(f.to_radians().sin().into())
+    fn sin(&self) -> Result<YololValue, YololError> {
+        Ok(Self(fixed::to_raw(fixed::sin(self.0))).into())
     }
 
-    fn asin(&self) -> Option<YololValue> {
-        let f: f64 = self.into();
-        if !(-1. ..=1.).contains(&f) {
-            return Some(Self(-9223372036854775808).into());
-        }
-        Some(f.asin().to_degrees().into())
+    fn asin(&self) -> Result<YololValue, YololError> {
+        Ok(fixed::asin_degrees(self.0).map_or(Self::MIN, Self).into())
     }
 
-    fn cos(&self) -> Option<YololValue> {
-        let f: f64 = self.into();
-        Some(f.to_radians().cos().into())
+    fn cos(&self) -> Result<YololValue, YololError> {
+        Ok(Self(fixed::to_raw(fixed::cos(self.0))).into())
     }
 
-    fn acos(&self) -> Option<YololValue> {
-        let f: f64 = self.into();
-        if !(-1. ..=1.).contains(&f) {
-            return Some(Self(-9223372036854775808).into());
-        }
-        Some(f.acos().to_degrees().into())
+    fn acos(&self) -> Result<YololValue, YololError> {
+        Ok(fixed::acos_degrees(self.0).map_or(Self::MIN, Self).into())
     }
 
-    fn tan(&self) -> Option<YololValue> {
-        let sin = &self.sin()?;
-        let cos = &self.cos()?;
-        if cos == &0.into() {
-            Some(Self(-22877332428).into())
+    fn tan(&self) -> Result<YololValue, YololError> {
+        let sin = fixed::sin(self.0);
+        let cos = fixed::cos(self.0);
+        if fixed::to_raw(cos) == 0 {
+            Ok(Self::TAN_ASYMPTOTE.into())
         } else {
-            sin / cos
+            Ok(Self(fixed::div_round(sin * 1000, cos) as i64).into())
         }
     }
 
-    fn atan(&self) -> Option<YololValue> {
-        let f: f64 = self.into();
-        Some(f.atan().to_degrees().into())
+    fn atan(&self) -> Result<YololValue, YololError> {
+        Ok(Self(fixed::atan_degrees(self.0)).into())
     }
 
-    fn pow(&self, e: &YololValue) -> Option<YololValue> {
+    fn pow(&self, e: &YololValue) -> Result<YololValue, YololError> {
         match e {
-            YololValue::String(_) => None,
-            YololValue::Int(v) => {
-                let r = (self.0 as f64 / 1000.).powf(v.0 as f64 / 1000.);
-                if !r.is_normal() || r >= 9223372036854775.807 || r <= -9223372036854775.808 {
-                    return Some(Self(-9223372036854775808).into());
-                }
-                Some(r.into())
-            }
+            YololValue::String(_) => Err(YololError::StringOperation { op: "^" }),
+            YololValue::Int(v) => Ok(fixed::pow(self.0, v.0).map_or(Self::MIN, Self).into()),
         }
     }
 
@@ -148,14 +156,14 @@ impl ValueTrait for YololInt {
 impl Add for &YololInt {
     type Output = YololInt;
     fn add(self, rhs: Self) -> Self::Output {
-        YololInt(self.0.saturating_add(rhs.0))
+        YololInt(overflow::ADD.apply(self.0 as i128 + rhs.0 as i128))
     }
 }
 
 impl Sub for &YololInt {
     type Output = YololInt;
     fn sub(self, rhs: Self) -> Self::Output {
-        YololInt(self.0.saturating_sub(rhs.0))
+        YololInt(overflow::SUB.apply(self.0 as i128 - rhs.0 as i128))
     }
 }
 
@@ -164,36 +172,30 @@ impl Mul for &YololInt {
 
     #[allow(clippy::suspicious_arithmetic_impl)]
     fn mul(self, rhs: Self) -> Self::Output {
-        let mut r = self.0;
-        r = r.wrapping_mul(rhs.0);
-        YololInt((r / 1000) as i64)
+        let r = overflow::MUL.apply(self.0 as i128 * rhs.0 as i128);
+        YololInt(r / YololInt::SCALE)
     }
 }
 
 impl Div for &YololInt {
-    type Output = Option<YololInt>;
+    type Output = Result<YololInt, YololError>;
     fn div(self, rhs: Self) -> Self::Output {
         if rhs == &0.into() {
-            return None;
+            return Err(YololError::DivideByZero);
         }
-        Some(YololInt::from(
-            (self.0 as f64 / 1000.) / (rhs.0 as f64 / 1000.),
-        ))
+        Ok(YololInt(overflow::DIV.apply(
+            self.0 as i128 * YololInt::SCALE as i128 / rhs.0 as i128,
+        )))
     }
 }
 
 impl Rem for &YololInt {
-    type Output = Option<YololInt>;
+    type Output = Result<YololInt, YololError>;
     fn rem(self, rhs: Self) -> Self::Output {
         if rhs == &0.into() {
-            return None;
-        } /*else if self < &0.into() {
-              Some(self.clone())
-          } else {
-              Some(YololInt(self.0 % rhs.0))
-          }*/
-        let d = self.0 / rhs.0 + (self.0 > 0) as i64;
-        Some(YololInt(self.0 - rhs.0 * d))
+            return Err(YololError::DivideByZero);
+        }
+        Ok(YololInt(self.0.wrapping_rem(rhs.0)))
     }
 }
 
@@ -206,17 +208,34 @@ impl Not for &YololInt {
 
 impl Display for YololInt {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
-        let d = self.0 / 1000;
-        let mut r = (self.0 - d * 1000).abs();
-        if r < 10 {
-            r *= 100;
-        } else if r < 100 {
-            r *= 10;
-        }
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let d = self.0.unsigned_abs() / Self::SCALE as u64;
+        let r = self.0.unsigned_abs() % Self::SCALE as u64;
         if r == 0 {
-            f.write_fmt(format_args!("{}", d))
+            f.write_fmt(format_args!("{}{}", sign, d))
         } else {
-            f.write_fmt(format_args!("{}.{}", d, r))
+            let r = format!("{:03}", r);
+            f.write_fmt(format_args!("{}{}.{}", sign, d, r.trim_end_matches('0')))
+        }
+    }
+}
+
+/// Serialized as its decimal string, `"1.5"`, so that no precision is lost in formats that
+/// store numbers as floats.
+#[cfg(feature = "serde")]
+impl serde::Serialize for YololInt {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for YololInt {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        match YololValue::parse_literal(&s) {
+            Ok(YololValue::Int(v)) => Ok(v),
+            _ => Err(serde::de::Error::custom(format!("invalid number {:?}", s))),
         }
     }
 }
@@ -253,12 +272,231 @@ impl From<bool> for YololInt {
 
 impl From<i64> for YololInt {
     fn from(v: i64) -> Self {
-        Self(v * 1000)
+        Self(overflow::CONVERSION.apply(v as i128 * Self::SCALE as i128))
     }
 }
 
 impl From<f64> for YololInt {
     fn from(v: f64) -> Self {
-        Self((v * 1000.).round() as i64)
+        let v = (v * 1000.).round();
+        match overflow::CONVERSION {
+            Overflow::Saturate => Self(v as i64),
+            Overflow::Wrap => Self(v as i128 as i64),
+        }
     }
 }
+
+#[test]
+fn div_test() {
+    let div = |a: i64, b: i64| (&YololInt(a) / &YololInt(b)).unwrap().0;
+    assert_eq!(div(1000, 3000), 333);
+    assert_eq!(div(2000, 3000), 666);
+    assert_eq!(div(-2000, 3000), -666);
+    assert_eq!(div(1, 1000), 1);
+    assert_eq!(
+        div(9_223_372_036_854_775_807, 1000),
+        9_223_372_036_854_775_807
+    );
+    assert_eq!(
+        div(9_223_372_036_854_775_807, 2000),
+        4_611_686_018_427_387_903
+    );
+    assert_eq!(div(9_007_199_254_740_993, 1000), 9_007_199_254_740_993);
+    assert!((&YololInt(1000) / &YololInt(0)).is_err());
+}
+
+#[test]
+fn rem_test() {
+    let rem = |a: i64, b: i64| (&YololInt(a) % &YololInt(b)).unwrap().0;
+    assert_eq!(rem(5000, 3000), 2000);
+    assert_eq!(rem(-5000, 3000), -2000);
+    assert_eq!(rem(5000, -3000), 2000);
+    assert_eq!(rem(5500, 2000), 1500);
+    assert_eq!(rem(i64::MIN, -1), 0);
+    assert!((&YololInt(1000) % &YololInt(0)).is_err());
+}
+
+#[test]
+fn inc_dec_test() {
+    let mut a = YololInt::from(5);
+    assert_eq!(a.post_inc(), YololInt::from(5).into());
+    assert_eq!(a, 6.into());
+    assert_eq!(a.pre_inc(), YololInt::from(7).into());
+    assert_eq!(a, 7.into());
+    assert_eq!(a.post_dec().unwrap(), YololInt::from(7).into());
+    assert_eq!(a, 6.into());
+    assert_eq!(a.pre_dec().unwrap(), YololInt::from(5).into());
+    assert_eq!(a, 5.into());
+}
+
+#[test]
+fn sqrt_test() {
+    use std::convert::TryFrom;
+
+    let sqrt = |a: i64| YololInt::try_from(&YololInt(a).sqrt().unwrap()).unwrap().0;
+    assert_eq!(sqrt(4000), 2000);
+    assert_eq!(sqrt(2000), 1414);
+    assert_eq!(sqrt(3000), 1732);
+    assert_eq!(sqrt(1), 32);
+    assert_eq!(sqrt(9_223_372_036_854_774_999), 96_038_388_350);
+    assert_eq!(sqrt(overflow::SQRT_LIMIT), YololInt::MIN.0);
+    assert_eq!(sqrt(-1000), -9223372036854775808);
+}
+
+#[test]
+fn trig_test() {
+    use std::convert::TryFrom;
+
+    let sin = |a: i64| YololInt::try_from(&YololInt(a).sin().unwrap()).unwrap().0;
+    let cos = |a: i64| YololInt::try_from(&YololInt(a).cos().unwrap()).unwrap().0;
+    let tan = |a: i64| YololInt::try_from(&YololInt(a).tan().unwrap()).unwrap().0;
+    assert_eq!(sin(0), 0);
+    assert_eq!(sin(30_000), 500);
+    assert_eq!(sin(45_000), 707);
+    assert_eq!(sin(90_000), 1000);
+    assert_eq!(sin(180_000), 0);
+    assert_eq!(sin(270_000), -1000);
+    assert_eq!(sin(-30_000), -500);
+    assert_eq!(sin(389_500), 492);
+    assert_eq!(sin(1), 0);
+    assert_eq!(sin(29), 1);
+    assert_eq!(cos(60_000), 500);
+    assert_eq!(cos(-60_000), 500);
+    assert_eq!(cos(180_000), -1000);
+    assert_eq!(tan(45_000), 1000);
+    assert_eq!(tan(89_000), 57290);
+    assert_eq!(tan(90_000), -22877332428);
+}
+
+#[test]
+fn inverse_trig_test() {
+    use std::convert::TryFrom;
+
+    let asin = |a: i64| YololInt::try_from(&YololInt(a).asin().unwrap()).unwrap().0;
+    let acos = |a: i64| YololInt::try_from(&YololInt(a).acos().unwrap()).unwrap().0;
+    let atan = |a: i64| YololInt::try_from(&YololInt(a).atan().unwrap()).unwrap().0;
+    assert_eq!(asin(0), 0);
+    assert_eq!(asin(500), 30_000);
+    assert_eq!(asin(1000), 90_000);
+    assert_eq!(asin(-1000), -90_000);
+    assert_eq!(asin(707), 44_991);
+    assert_eq!(asin(1001), YololInt::MIN.0);
+    assert_eq!(acos(500), 60_000);
+    assert_eq!(acos(-1000), 180_000);
+    assert_eq!(acos(-1001), YololInt::MIN.0);
+    assert_eq!(atan(1000), 45_000);
+    assert_eq!(atan(-1000), -45_000);
+    assert_eq!(atan(1), 57);
+    assert_eq!(atan(1_000_000), 89_943);
+}
+
+#[test]
+fn pow_test() {
+    use std::convert::TryFrom;
+
+    let pow = |a: i64, b: i64| {
+        YololInt::try_from(&YololInt(a).pow(&YololInt(b).into()).unwrap())
+            .unwrap()
+            .0
+    };
+    assert_eq!(pow(2000, 10_000), 1_024_000);
+    assert_eq!(pow(-2000, 3000), -8000);
+    assert_eq!(pow(10_000, -2000), 10);
+    assert_eq!(pow(2000, 500), 1414);
+    assert_eq!(pow(1500, 2500), 2756);
+    assert_eq!(pow(1001, 1_000_000), 2717);
+    assert_eq!(pow(0, 2000), 0);
+    assert_eq!(pow(0, 0), 1000);
+    assert_eq!(pow(0, -1000), YololInt::MIN.0);
+    assert_eq!(pow(-2000, 500), YololInt::MIN.0);
+    assert_eq!(pow(10_000, 16_000), YololInt::MIN.0);
+    assert_eq!(pow(10_000, -16_000), 0);
+    assert_eq!(pow(500, -1_000_000), YololInt::MIN.0);
+}
+
+#[test]
+fn overflow_add_sub_test() {
+    let add = |a: i64, b: i64| (&YololInt(a) + &YololInt(b)).0;
+    let sub = |a: i64, b: i64| (&YololInt(a) - &YololInt(b)).0;
+    assert_eq!(add(i64::MAX, 1), i64::MAX);
+    assert_eq!(add(i64::MAX, i64::MAX), i64::MAX);
+    assert_eq!(add(i64::MIN, -1), i64::MIN);
+    assert_eq!(add(i64::MIN, i64::MIN), i64::MIN);
+    assert_eq!(add(i64::MAX, i64::MIN), -1);
+    assert_eq!(sub(i64::MIN, 1), i64::MIN);
+    assert_eq!(sub(i64::MAX, -1), i64::MAX);
+    assert_eq!(sub(0, i64::MIN), i64::MAX);
+    assert_eq!(sub(-1, i64::MAX), i64::MIN);
+}
+
+#[test]
+fn mul_test() {
+    let mul = |a: f64, b: f64| &YololInt::from(a) * &YololInt::from(b);
+    assert_eq!(mul(2.5, 4.0), YololInt::from(10.0));
+    assert_eq!(mul(-1.5, 1.5), YololInt::from(-2.25));
+    assert_eq!(mul(0.001, 0.5), YololInt::from(0.0));
+}
+
+#[test]
+fn overflow_mul_test() {
+    let mul = |a: i64, b: i64| (&YololInt(a) * &YololInt(b)).0;
+    assert_eq!(mul(2000, 3000), 6000);
+    assert_eq!(mul(-2000, 3000), -6000);
+    assert_eq!(mul(i64::MAX, 1000), i64::MAX.wrapping_mul(1000) / 1000);
+    assert_eq!(mul(i64::MAX, 2000), (i64::MAX.wrapping_mul(2000)) / 1000);
+    assert_eq!(mul(i64::MIN, -1000), i64::MIN.wrapping_mul(-1000) / 1000);
+    assert_eq!(
+        mul(4_000_000_000_000, 4_000_000_000_000),
+        -4_833_260_864_108_756
+    );
+}
+
+#[test]
+fn overflow_div_test() {
+    let div = |a: i64, b: i64| (&YololInt(a) / &YololInt(b)).unwrap().0;
+    assert_eq!(div(i64::MAX, 1), i64::MAX);
+    assert_eq!(div(i64::MIN, 1), i64::MIN);
+    assert_eq!(div(i64::MIN, -1), i64::MAX);
+    assert_eq!(div(i64::MIN, -1000), i64::MAX);
+    assert_eq!(div(i64::MIN, 1000), i64::MIN);
+}
+
+#[test]
+fn overflow_conversion_test() {
+    assert_eq!(YololInt::from(i64::MAX), YololInt::MAX);
+    assert_eq!(YololInt::from(i64::MIN), YololInt::MIN);
+    assert_eq!(
+        YololInt::from(9_223_372_036_854_775),
+        YololInt(9_223_372_036_854_775_000)
+    );
+    assert_eq!(YololInt::from(1e300), YololInt::MAX);
+    assert_eq!(YololInt::from(-1e300), YololInt::MIN);
+}
+
+#[test]
+fn overflow_fac_test() {
+    use std::convert::TryFrom;
+
+    let fac = |a: i64| {
+        YololInt::try_from(&YololInt::from(a).fac().unwrap())
+            .unwrap()
+            .0
+    };
+    assert_eq!(fac(0), 1000);
+    assert_eq!(fac(20), 2_432_902_008_176_640_000i64.wrapping_mul(1000));
+    assert_eq!(fac(25), 7_034_535_277_573_963_776i64.wrapping_mul(1000));
+    assert_eq!(fac(-1), i64::MIN);
+}
+
+#[test]
+fn display_test() {
+    assert_eq!(YololInt(0).to_string(), "0");
+    assert_eq!(YololInt(1000).to_string(), "1");
+    assert_eq!(YololInt(1500).to_string(), "1.5");
+    assert_eq!(YololInt(1005).to_string(), "1.005");
+    assert_eq!(YololInt(1050).to_string(), "1.05");
+    assert_eq!(YololInt(-500).to_string(), "-0.5");
+    assert_eq!(YololInt(-1005).to_string(), "-1.005");
+    assert_eq!(YololInt::MIN.to_string(), "-9223372036854775.808");
+    assert_eq!(YololInt::MAX.to_string(), "9223372036854775.807");
+}
@@ -0,0 +1,153 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+use super::overflow;
+use super::YololInt;
+use super::YololValue;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LiteralError {
+    Empty,
+    InvalidNumber(String),
+    UnterminatedString,
+    InvalidEscape(char),
+    TrailingCharacters(String),
+}
+
+impl Display for LiteralError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LiteralError::Empty => write!(f, "empty literal"),
+            LiteralError::InvalidNumber(s) => write!(f, "invalid number {:?}", s),
+            LiteralError::UnterminatedString => write!(f, "unterminated string"),
+            LiteralError::InvalidEscape(c) => write!(f, "invalid escape \\{}", c),
+            LiteralError::TrailingCharacters(s) => {
+                write!(f, "unexpected {:?} after the literal", s)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LiteralError {}
+
+impl YololValue {
+    /// Parse a YOLOL literal: a number like `-12.5` or a quoted string like `"a\"b"`.
+    /// Decimals past the third are truncated, as the game does.
+    pub fn parse_literal(literal: &str) -> Result<Self, LiteralError> {
+        let literal = literal.trim();
+        match literal.chars().next() {
+            None => Err(LiteralError::Empty),
+            Some('"') => parse_string(&literal[1..]),
+            Some(_) => parse_number(literal),
+        }
+    }
+}
+
+impl FromStr for YololValue {
+    type Err = LiteralError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_literal(s)
+    }
+}
+
+fn parse_number(literal: &str) -> Result<YololValue, LiteralError> {
+    let invalid = || LiteralError::InvalidNumber(literal.to_string());
+    let (negative, digits) = match literal.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, literal),
+    };
+    let (int, frac) = match digits.find('.') {
+        Some(i) => (&digits[..i], &digits[i + 1..]),
+        None => (digits, ""),
+    };
+    if int.is_empty() && frac.is_empty() {
+        return Err(invalid());
+    }
+    let mut raw: i128 = 0;
+    for c in int.chars() {
+        let digit = c.to_digit(10).ok_or_else(invalid)?;
+        raw = (raw * 10 + digit as i128).min(i64::MAX as i128 + 1);
+    }
+    raw *= YololInt::SCALE as i128;
+    let mut unit = YololInt::SCALE as i128;
+    for c in frac.chars() {
+        let digit = c.to_digit(10).ok_or_else(invalid)?;
+        unit /= 10;
+        raw += digit as i128 * unit;
+    }
+    if negative {
+        raw = -raw;
+    }
+    Ok(YololInt::new_raw(overflow::CONVERSION.apply(raw)).into())
+}
+
+fn parse_string(body: &str) -> Result<YololValue, LiteralError> {
+    let mut s = String::new();
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                let rest = &body[i + 1..];
+                if !rest.is_empty() {
+                    return Err(LiteralError::TrailingCharacters(rest.to_string()));
+                }
+                return Ok(s.as_str().into());
+            }
+            '\\' => match chars.next() {
+                Some((_, '"')) => s.push('"'),
+                Some((_, '\\')) => s.push('\\'),
+                Some((_, 'n')) => s.push('\n'),
+                Some((_, 't')) => s.push('\t'),
+                Some((_, c)) => return Err(LiteralError::InvalidEscape(c)),
+                None => return Err(LiteralError::UnterminatedString),
+            },
+            c => s.push(c),
+        }
+    }
+    Err(LiteralError::UnterminatedString)
+}
+
+#[test]
+fn parse_number_test() {
+    let parse = |s: &str| YololValue::parse_literal(s).unwrap();
+    assert_eq!(parse("12"), 12.into());
+    assert_eq!(parse("-12.5"), (-12.5).into());
+    assert_eq!(parse("0.001"), YololInt::new_raw(1).into());
+    assert_eq!(parse(".5"), 0.5.into());
+    assert_eq!(parse("1.23456"), YololInt::new_raw(1234).into());
+    assert_eq!(parse("-1.9999"), YololInt::new_raw(-1999).into());
+    assert_eq!(parse("99999999999999999999"), YololInt::MAX.into());
+    assert_eq!(parse("-99999999999999999999"), YololInt::MIN.into());
+    assert!(YololValue::parse_literal("1a").is_err());
+    assert!(YololValue::parse_literal("-").is_err());
+    assert_eq!(YololValue::parse_literal(" "), Err(LiteralError::Empty));
+}
+
+#[test]
+fn parse_string_test() {
+    let parse = |s: &str| YololValue::parse_literal(s).unwrap();
+    assert_eq!(parse("\"hello\""), "hello".into());
+    assert_eq!(parse("\"\""), "".into());
+    assert_eq!(parse(r#""a\"b\\c""#), "a\"b\\c".into());
+    assert_eq!(
+        YololValue::parse_literal("\"abc"),
+        Err(LiteralError::UnterminatedString)
+    );
+    assert_eq!(
+        YololValue::parse_literal(r#""a\qb""#),
+        Err(LiteralError::InvalidEscape('q'))
+    );
+    assert!("\"a\" b".parse::<YololValue>().is_err());
+}
+
+#[test]
+fn literal_round_trip_test() {
+    let mut s: String = (0..0x20u8).map(char::from).collect();
+    s.push_str("\"\\\u{7f}é");
+    for value in [YololValue::from(s.as_str()), (-1.5).into()] {
+        assert_eq!(YololValue::parse_literal(&value.to_literal()), Ok(value));
+    }
+    assert_eq!(YololValue::from("a\"\n").to_literal(), r#""a\"\n""#);
+}
@@ -1,9 +1,17 @@
+mod error;
+mod fixed;
 mod int;
+mod literal;
+mod op;
+pub mod overflow;
 mod string;
 
+use std::cmp::Ordering;
 use std::convert::TryFrom;
 use std::convert::TryInto;
 use std::fmt::Display;
+use std::hash::Hash;
+use std::hash::Hasher;
 use std::ops::Add;
 use std::ops::Div;
 use std::ops::Mul;
@@ -12,30 +20,37 @@ use std::ops::Sub;
 
 use enum_dispatch::enum_dispatch;
 
+pub use self::error::YololError;
 pub use self::int::YololInt;
+pub use self::literal::LiteralError;
+pub use self::op::BinaryOp;
+pub use self::op::IncDecOp;
+pub use self::op::UnaryOp;
 pub use self::string::YololString;
 
 #[enum_dispatch]
 pub trait ValueTrait {
     fn post_inc(&mut self) -> YololValue;
     fn pre_inc(&mut self) -> YololValue;
-    fn post_dec(&mut self) -> Option<YololValue>;
-    fn pre_dec(&mut self) -> Option<YololValue>;
-    fn fac(&self) -> Option<YololValue>;
-    fn abs(&self) -> Option<YololValue>;
-    fn sqrt(&self) -> Option<YololValue>;
-    fn sin(&self) -> Option<YololValue>;
-    fn asin(&self) -> Option<YololValue>;
-    fn cos(&self) -> Option<YololValue>;
-    fn acos(&self) -> Option<YololValue>;
-    fn tan(&self) -> Option<YololValue>;
-    fn atan(&self) -> Option<YololValue>;
-    fn pow(&self, e: &YololValue) -> Option<YololValue>;
+    fn post_dec(&mut self) -> Result<YololValue, YololError>;
+    fn pre_dec(&mut self) -> Result<YololValue, YololError>;
+    fn fac(&self) -> Result<YololValue, YololError>;
+    fn abs(&self) -> Result<YololValue, YololError>;
+    fn sqrt(&self) -> Result<YololValue, YololError>;
+    fn sin(&self) -> Result<YololValue, YololError>;
+    fn asin(&self) -> Result<YololValue, YololError>;
+    fn cos(&self) -> Result<YololValue, YololError>;
+    fn acos(&self) -> Result<YololValue, YololError>;
+    fn tan(&self) -> Result<YololValue, YololError>;
+    fn atan(&self) -> Result<YololValue, YololError>;
+    fn pow(&self, e: &YololValue) -> Result<YololValue, YololError>;
     fn not(&self) -> YololValue;
 }
 
 #[enum_dispatch(ValueTrait)]
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum YololValue {
     String(YololString),
     Int(YololInt),
@@ -124,43 +139,41 @@ impl YololValue {
 }
 
 impl Mul for &YololValue {
-    type Output = Option<YololValue>;
+    type Output = Result<YololValue, YololError>;
 
     fn mul(self, rhs: Self) -> Self::Output {
         if let YololValue::Int(lhs) = self {
             if let YololValue::Int(rhs) = rhs {
-                return Some((lhs * rhs).into());
+                return Ok((lhs * rhs).into());
             }
         }
-        None
+        Err(YololError::StringOperation { op: "*" })
     }
 }
 
 impl Div for &YololValue {
-    type Output = Option<YololValue>;
+    type Output = Result<YololValue, YololError>;
 
     fn div(self, rhs: Self) -> Self::Output {
         if let YololValue::Int(lhs) = self {
             if let YololValue::Int(rhs) = rhs {
-                return Some((lhs / rhs)?.into());
+                return Ok((lhs / rhs)?.into());
             }
         }
-        None
+        Err(YololError::StringOperation { op: "/" })
     }
 }
 
 impl Rem for &YololValue {
-    type Output = Option<YololValue>;
+    type Output = Result<YololValue, YololError>;
 
     fn rem(self, rhs: Self) -> Self::Output {
         if let YololValue::Int(lhs) = self {
             if let YololValue::Int(rhs) = rhs {
-                if rhs != &0.into() {
-                    return Some((lhs % rhs)?.into());
-                }
+                return Ok((lhs % rhs)?.into());
             }
         }
-        None
+        Err(YololError::StringOperation { op: "%" })
     }
 }
 
@@ -195,7 +208,7 @@ impl Add for &YololValue {
 }
 
 impl Sub for &YololValue {
-    type Output = Option<YololValue>;
+    type Output = Result<YololValue, YololError>;
 
     fn sub(self, rhs: Self) -> Self::Output {
         if self.is_string() || rhs.is_string() {
@@ -215,11 +228,11 @@ impl Sub for &YololValue {
                     b.into()
                 }
             };
-            Some((a - b)?.into())
+            Ok((a - b).into())
         } else {
             let a: YololInt = self.try_into().unwrap();
             let b: YololInt = rhs.try_into().unwrap();
-            Some((&a - &b).into())
+            Ok((&a - &b).into())
         }
     }
 }
@@ -239,34 +252,109 @@ impl PartialEq for YololValue {
     }
 }
 
+impl Eq for YololValue {}
+
+/// A total order to use values as map keys: ints sort before strings, ints by value and
+/// strings by bytes. YOLOL's own comparisons are `YololValue::yolol_lt` and friends.
 impl PartialOrd for YololValue {
-    fn partial_cmp(&self, rhs: &Self) -> Option<std::cmp::Ordering> {
-        if self.is_string() || rhs.is_string() {
-            let a: YololString = {
-                if self.is_string() {
-                    self.clone().try_into().unwrap()
-                } else {
-                    let a: &YololInt = &self.try_into().unwrap();
-                    a.into()
-                }
-            };
-            let b: YololString = {
-                if rhs.is_string() {
-                    rhs.try_into().unwrap()
-                } else {
-                    let b: &YololInt = &rhs.clone().try_into().unwrap();
-                    b.into()
-                }
-            };
-            a.partial_cmp(&b)
-        } else {
-            let a: YololInt = self.clone().try_into().unwrap();
-            let b: YololInt = rhs.clone().try_into().unwrap();
-            a.partial_cmp(&b)
+    fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> {
+        Some(self.cmp(rhs))
+    }
+}
+
+impl Ord for YololValue {
+    fn cmp(&self, rhs: &Self) -> Ordering {
+        match (self, rhs) {
+            (YololValue::Int(a), YololValue::Int(b)) => a.cmp(b),
+            (YololValue::String(a), YololValue::String(b)) => a.cmp(b),
+            (YololValue::Int(_), YololValue::String(_)) => Ordering::Less,
+            (YololValue::String(_), YololValue::Int(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl Hash for YololValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            YololValue::String(v) => {
+                state.write_u8(1);
+                v.hash(state)
+            }
+            YololValue::Int(v) => {
+                state.write_u8(0);
+                v.hash(state)
+            }
         }
     }
 }
 
+impl YololValue {
+    /// Mixed int and string comparisons convert the int to its string form and compare
+    /// the strings, as the game does.
+    fn compare(&self, rhs: &Self) -> Ordering {
+        match (self, rhs) {
+            (YololValue::Int(a), YololValue::Int(b)) => a.cmp(b),
+            (YololValue::String(a), YololValue::String(b)) => a.as_str().cmp(b),
+            (YololValue::String(a), YololValue::Int(b)) => {
+                a.as_str().cmp(YololString::from(b).as_str())
+            }
+            (YololValue::Int(a), YololValue::String(b)) => YololString::from(a).as_str().cmp(b),
+        }
+    }
+
+    /// YOLOL's `==`, returning 1 or 0. Unlike `PartialEq`, an int equals a string holding
+    /// its string form.
+    pub fn yolol_eq(&self, rhs: &Self) -> Self {
+        (self.compare(rhs) == Ordering::Equal).into()
+    }
+
+    /// YOLOL's `!=`, returning 1 or 0.
+    pub fn yolol_ne(&self, rhs: &Self) -> Self {
+        (self.compare(rhs) != Ordering::Equal).into()
+    }
+
+    /// YOLOL's `<`, returning 1 or 0.
+    pub fn yolol_lt(&self, rhs: &Self) -> Self {
+        (self.compare(rhs) == Ordering::Less).into()
+    }
+
+    /// YOLOL's `<=`, returning 1 or 0.
+    pub fn yolol_le(&self, rhs: &Self) -> Self {
+        (self.compare(rhs) != Ordering::Greater).into()
+    }
+
+    /// YOLOL's `>`, returning 1 or 0.
+    pub fn yolol_gt(&self, rhs: &Self) -> Self {
+        (self.compare(rhs) == Ordering::Greater).into()
+    }
+
+    /// YOLOL's `>=`, returning 1 or 0.
+    pub fn yolol_ge(&self, rhs: &Self) -> Self {
+        (self.compare(rhs) != Ordering::Less).into()
+    }
+
+    /// Format the value as a literal: numbers as they are and strings quoted, so that `1`
+    /// and `"1"` can be told apart. `parse_literal` reads it back.
+    pub fn to_literal(&self) -> String {
+        let v = match self {
+            YololValue::Int(v) => return v.to_string(),
+            YololValue::String(v) => v,
+        };
+        let mut literal = String::from("\"");
+        for c in v.chars() {
+            match c {
+                '"' => literal.push_str("\\\""),
+                '\\' => literal.push_str("\\\\"),
+                '\n' => literal.push_str("\\n"),
+                '\t' => literal.push_str("\\t"),
+                c => literal.push(c),
+            }
+        }
+        literal.push('"');
+        literal
+    }
+}
+
 impl Display for YololValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -275,3 +363,66 @@ impl Display for YololValue {
         }
     }
 }
+
+#[test]
+fn comparison_test() {
+    let one: YololValue = 1.into();
+    let half: YololValue = 0.5.into();
+    let s = |v: &str| YololValue::from(v);
+    assert_eq!(one.yolol_eq(&1.into()), 1.into());
+    assert_eq!(one.yolol_eq(&s("1")), 1.into());
+    assert_eq!(half.yolol_eq(&s("0.5")), 1.into());
+    assert_eq!(one.yolol_ne(&s("1.0")), 1.into());
+    assert_eq!(one.yolol_lt(&half), 0.into());
+    assert_eq!(half.yolol_le(&one), 1.into());
+    assert_eq!(s("10").yolol_lt(&s("9")), 1.into());
+    assert_eq!(YololValue::from(10).yolol_gt(&s("9")), 0.into());
+    assert_eq!(s("b").yolol_ge(&s("a")), 1.into());
+    assert!(one != s("1"));
+    assert_eq!(half.to_literal(), "0.5");
+    assert_eq!(s("0.5").to_literal(), "\"0.5\"");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_test() {
+    let values: Vec<YololValue> = vec![
+        YololInt::new_raw(9_007_199_254_740_993).into(),
+        (-1.5).into(),
+        "1.5".into(),
+    ];
+    let json = serde_json::to_string(&values).unwrap();
+    assert_eq!(
+        json,
+        r#"[{"int":"9007199254740.993"},{"int":"-1.5"},{"string":"1.5"}]"#
+    );
+    let back: Vec<YololValue> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, values);
+}
+
+#[test]
+fn total_order_test() {
+    use std::collections::BTreeSet;
+    use std::collections::HashSet;
+
+    let values: Vec<YololValue> = vec!["b".into(), 2.into(), "10".into(), (-1).into(), 2.into()];
+    let sorted: Vec<YololValue> = values
+        .iter()
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    assert_eq!(sorted, vec![(-1).into(), 2.into(), "10".into(), "b".into()]);
+    let hashed: HashSet<YololValue> = values.into_iter().collect();
+    assert_eq!(hashed.len(), 4);
+    assert!(hashed.contains(&"10".into()));
+    assert!(!hashed.contains(&10.into()));
+
+    // Every int sorts before every string, even where YOLOL compares them the other way.
+    let ten = YololValue::from(10);
+    let one = YololValue::from("1");
+    assert!(ten < one);
+    assert_eq!(ten.cmp(&one), Ordering::Less);
+    assert_eq!(ten.yolol_lt(&one), 0.into());
+    assert_eq!(ten.yolol_gt(&one), 1.into());
+}
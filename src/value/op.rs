@@ -0,0 +1,255 @@
+use super::ValueTrait;
+use super::YololError;
+use super::YololInt;
+use super::YololValue;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    pub const ALL: [BinaryOp; 14] = [
+        BinaryOp::Add,
+        BinaryOp::Sub,
+        BinaryOp::Mul,
+        BinaryOp::Div,
+        BinaryOp::Mod,
+        BinaryOp::Pow,
+        BinaryOp::Eq,
+        BinaryOp::Ne,
+        BinaryOp::Lt,
+        BinaryOp::Le,
+        BinaryOp::Gt,
+        BinaryOp::Ge,
+        BinaryOp::And,
+        BinaryOp::Or,
+    ];
+
+    /// Get the operator as written in YOLOL.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+            BinaryOp::Pow => "^",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "and",
+            BinaryOp::Or => "or",
+        }
+    }
+
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        let symbol = symbol.to_lowercase();
+        Self::ALL.iter().copied().find(|op| op.symbol() == symbol)
+    }
+
+    /// Get the operator of a compound assignment like `+=`, if YOLOL has one.
+    pub fn from_assign_symbol(symbol: &str) -> Option<Self> {
+        match symbol {
+            "+=" => Some(BinaryOp::Add),
+            "-=" => Some(BinaryOp::Sub),
+            "*=" => Some(BinaryOp::Mul),
+            "/=" => Some(BinaryOp::Div),
+            "%=" => Some(BinaryOp::Mod),
+            "^=" => Some(BinaryOp::Pow),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+    Fac,
+    Abs,
+    Sqrt,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+}
+
+impl UnaryOp {
+    pub const ALL: [UnaryOp; 11] = [
+        UnaryOp::Neg,
+        UnaryOp::Not,
+        UnaryOp::Fac,
+        UnaryOp::Abs,
+        UnaryOp::Sqrt,
+        UnaryOp::Sin,
+        UnaryOp::Cos,
+        UnaryOp::Tan,
+        UnaryOp::Asin,
+        UnaryOp::Acos,
+        UnaryOp::Atan,
+    ];
+
+    /// Get the operator as written in YOLOL. `!` is the postfix factorial.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            UnaryOp::Neg => "-",
+            UnaryOp::Not => "not",
+            UnaryOp::Fac => "!",
+            UnaryOp::Abs => "abs",
+            UnaryOp::Sqrt => "sqrt",
+            UnaryOp::Sin => "sin",
+            UnaryOp::Cos => "cos",
+            UnaryOp::Tan => "tan",
+            UnaryOp::Asin => "asin",
+            UnaryOp::Acos => "acos",
+            UnaryOp::Atan => "atan",
+        }
+    }
+
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        let symbol = symbol.to_lowercase();
+        Self::ALL.iter().copied().find(|op| op.symbol() == symbol)
+    }
+}
+
+/// The increment and decrement operators, which modify the variable they apply to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IncDecOp {
+    PreInc,
+    PostInc,
+    PreDec,
+    PostDec,
+}
+
+impl YololValue {
+    pub fn apply_binary(&self, op: BinaryOp, rhs: &Self) -> Result<Self, YololError> {
+        match op {
+            BinaryOp::Add => Ok(self + rhs),
+            BinaryOp::Sub => self - rhs,
+            BinaryOp::Mul => self * rhs,
+            BinaryOp::Div => self / rhs,
+            BinaryOp::Mod => self % rhs,
+            BinaryOp::Pow => self.pow(rhs),
+            BinaryOp::Eq => Ok(self.yolol_eq(rhs)),
+            BinaryOp::Ne => Ok(self.yolol_ne(rhs)),
+            BinaryOp::Lt => Ok(self.yolol_lt(rhs)),
+            BinaryOp::Le => Ok(self.yolol_le(rhs)),
+            BinaryOp::Gt => Ok(self.yolol_gt(rhs)),
+            BinaryOp::Ge => Ok(self.yolol_ge(rhs)),
+            BinaryOp::And => Ok(self.and(rhs)),
+            BinaryOp::Or => Ok(self.or(rhs)),
+        }
+    }
+
+    pub fn apply_unary(&self, op: UnaryOp) -> Result<Self, YololError> {
+        match op {
+            UnaryOp::Neg => self.neg(),
+            UnaryOp::Not => Ok(ValueTrait::not(self)),
+            UnaryOp::Fac => self.fac(),
+            UnaryOp::Abs => self.abs(),
+            UnaryOp::Sqrt => self.sqrt(),
+            UnaryOp::Sin => self.sin(),
+            UnaryOp::Cos => self.cos(),
+            UnaryOp::Tan => self.tan(),
+            UnaryOp::Asin => self.asin(),
+            UnaryOp::Acos => self.acos(),
+            UnaryOp::Atan => self.atan(),
+        }
+    }
+
+    /// Apply `op` in place and return the value of the expression.
+    pub fn apply_inc_dec(&mut self, op: IncDecOp) -> Result<Self, YololError> {
+        match op {
+            IncDecOp::PreInc => Ok(self.pre_inc()),
+            IncDecOp::PostInc => Ok(self.post_inc()),
+            IncDecOp::PreDec => self.pre_dec(),
+            IncDecOp::PostDec => self.post_dec(),
+        }
+    }
+
+    /// The compound assignment `self op= rhs`. On error `self` is left unchanged.
+    pub fn apply_assign(&mut self, op: BinaryOp, rhs: &Self) -> Result<(), YololError> {
+        *self = self.apply_binary(op, rhs)?;
+        Ok(())
+    }
+
+    /// Unary minus.
+    pub fn neg(&self) -> Result<Self, YololError> {
+        match self {
+            YololValue::Int(v) => Ok((&YololInt::default() - v).into()),
+            YololValue::String(_) => Err(YololError::StringOperation { op: "-" }),
+        }
+    }
+}
+
+#[test]
+fn apply_binary_test() {
+    let a: YololValue = 6.into();
+    let b: YololValue = 4.into();
+    let apply = |op| a.apply_binary(op, &b).unwrap();
+    assert_eq!(apply(BinaryOp::Add), 10.into());
+    assert_eq!(apply(BinaryOp::Sub), 2.into());
+    assert_eq!(apply(BinaryOp::Mul), 24.into());
+    assert_eq!(apply(BinaryOp::Div), 1.5.into());
+    assert_eq!(apply(BinaryOp::Mod), 2.into());
+    assert_eq!(apply(BinaryOp::Pow), 1296.into());
+    assert_eq!(apply(BinaryOp::Gt), 1.into());
+    assert_eq!(apply(BinaryOp::Le), 0.into());
+    assert_eq!(apply(BinaryOp::And), 1.into());
+    assert_eq!(
+        YololValue::from("a").apply_binary(BinaryOp::Mul, &b),
+        Err(YololError::StringOperation { op: "*" })
+    );
+    for op in BinaryOp::ALL.iter() {
+        assert_eq!(BinaryOp::from_symbol(op.symbol()), Some(*op));
+    }
+}
+
+#[test]
+fn apply_unary_test() {
+    let a: YololValue = 4.into();
+    assert_eq!(a.apply_unary(UnaryOp::Neg).unwrap(), (-4).into());
+    assert_eq!(a.apply_unary(UnaryOp::Sqrt).unwrap(), 2.into());
+    assert_eq!(a.apply_unary(UnaryOp::Fac).unwrap(), 24.into());
+    assert_eq!(a.apply_unary(UnaryOp::Not).unwrap(), 0.into());
+    assert!(YololValue::from("a").apply_unary(UnaryOp::Neg).is_err());
+    for op in UnaryOp::ALL.iter() {
+        assert_eq!(UnaryOp::from_symbol(op.symbol()), Some(*op));
+    }
+}
+
+#[test]
+fn apply_assign_test() {
+    let mut a: YololValue = 4.into();
+    a.apply_assign(BinaryOp::Pow, &2.into()).unwrap();
+    assert_eq!(a, 16.into());
+    assert!(a.apply_assign(BinaryOp::Div, &0.into()).is_err());
+    assert_eq!(a, 16.into());
+    assert_eq!(a.apply_inc_dec(IncDecOp::PostInc).unwrap(), 16.into());
+    assert_eq!(a.apply_inc_dec(IncDecOp::PreDec).unwrap(), 16.into());
+
+    let mut s = YololValue::from("ab");
+    assert_eq!(s.apply_inc_dec(IncDecOp::PostDec).unwrap(), "ab".into());
+    assert_eq!(s, "a".into());
+    s.apply_assign(BinaryOp::Add, &1.into()).unwrap();
+    assert_eq!(s, "a1".into());
+}
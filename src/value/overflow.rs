@@ -0,0 +1,36 @@
+//! How `YololInt` operations behave when their result does not fit in the raw `i64`.
+//!
+//! The game does not handle overflow the same way for every operation: additions and
+//! subtractions stick to the bounds, while multiplication and factorial wrap around. Each
+//! operation reads its policy from the constants below.
+
+/// What to do with a result outside of the raw `i64` range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Keep the low 64 bits of the result.
+    Wrap,
+    /// Clamp the result to `YololInt::MIN`/`YololInt::MAX`.
+    Saturate,
+}
+
+impl Overflow {
+    pub fn apply(self, v: i128) -> i64 {
+        match self {
+            Overflow::Wrap => v as i64,
+            Overflow::Saturate => v.clamp(i64::MIN as i128, i64::MAX as i128) as i64,
+        }
+    }
+}
+
+pub const ADD: Overflow = Overflow::Saturate;
+pub const SUB: Overflow = Overflow::Saturate;
+/// Applied to the product of the raw values, before it is scaled back down.
+pub const MUL: Overflow = Overflow::Wrap;
+pub const DIV: Overflow = Overflow::Saturate;
+/// Applied to the integer product, before it is scaled to a raw value.
+pub const FACTORIAL: Overflow = Overflow::Wrap;
+/// Applied when building a `YololInt` from an integer or a float.
+pub const CONVERSION: Overflow = Overflow::Saturate;
+/// Raw values from this one up make `sqrt` return `YololInt::MIN`, as the game does for
+/// 9223372036854775.000 and above.
+pub const SQRT_LIMIT: i64 = 9_223_372_036_854_775_000;
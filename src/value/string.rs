@@ -1,14 +1,19 @@
 use std::fmt::Display;
+use std::hash::Hash;
+use std::hash::Hasher;
 use std::ops::Add;
 use std::ops::Deref;
 use std::ops::DerefMut;
 use std::ops::Sub;
 
 use super::ValueTrait;
+use super::YololError;
 use super::YololInt;
 use super::YololValue;
 
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct YololString(String);
 
 impl ValueTrait for YololString {
@@ -22,62 +27,61 @@ impl ValueTrait for YololString {
         self.clone().into()
     }
 
-    fn post_dec(&mut self) -> Option<YololValue> {
+    fn post_dec(&mut self) -> Result<YololValue, YololError> {
         if self.0.is_empty() {
-            return None;
+            return Err(YololError::EmptyStringDecrement);
         }
         let org = self.clone();
-        *self = self.0[0..self.0.len() - 1].into();
-        Some(org.into())
+        self.0.pop();
+        Ok(org.into())
     }
 
-    fn pre_dec(&mut self) -> Option<YololValue> {
+    fn pre_dec(&mut self) -> Result<YololValue, YololError> {
         if self.0.is_empty() {
-            return None;
+            return Err(YololError::EmptyStringDecrement);
         }
-        *self = self.0[0..self.0.len() - 1].into();
-        Some(self.clone().into())
+        self.0.pop();
+        Ok(self.clone().into())
     }
 
-    fn fac(&self) -> Option<YololValue> {
-        //runtime error
-        None
+    fn fac(&self) -> Result<YololValue, YololError> {
+        Err(YololError::StringOperation { op: "!" })
     }
 
-    fn abs(&self) -> Option<YololValue> {
-        None
+    fn abs(&self) -> Result<YololValue, YololError> {
+        Err(YololError::StringOperation { op: "abs" })
     }
 
-    fn sqrt(&self) -> Option<YololValue> {
-        None
+    fn sqrt(&self) -> Result<YololValue, YololError> {
+        Err(YololError::StringOperation { op: "sqrt" })
     }
 
-    fn sin(&self) -> Option<YololValue> {
-        None
+    fn sin(&self) -> Result<YololValue, YololError> {
+        Err(YololError::StringOperation { op: "sin" })
     }
 
-    fn asin(&self) -> Option<YololValue> {
-        None
+    fn asin(&self) -> Result<YololValue, YololError> {
+        Err(YololError::StringOperation { op: "asin" })
     }
 
-    fn cos(&self) -> Option<YololValue> {
-        None
+    fn cos(&self) -> Result<YololValue, YololError> {
+        Err(YololError::StringOperation { op: "cos" })
     }
 
-    fn acos(&self) -> Option<YololValue> {
-        None
+    fn acos(&self) -> Result<YololValue, YololError> {
+        Err(YololError::StringOperation { op: "acos" })
     }
 
-    fn tan(&self) -> Option<YololValue> {
-        None
+    fn tan(&self) -> Result<YololValue, YololError> {
+        Err(YololError::StringOperation { op: "tan" })
     }
 
-    fn atan(&self) -> Option<YololValue> {
-        None
+    fn atan(&self) -> Result<YololValue, YololError> {
+        Err(YololError::StringOperation { op: "atan" })
     }
 
-    fn pow(&self, _: &YololValue) -> Option<YololValue> {
-        None
+    fn pow(&self, _: &YololValue) -> Result<YololValue, YololError> {
+        Err(YololError::StringOperation { op: "^" })
     }
 
     fn not(&self) -> YololValue {
@@ -91,9 +95,23 @@ impl PartialEq for YololString {
     }
 }
 
+impl Eq for YololString {}
+
 impl PartialOrd for YololString {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.0.partial_cmp(&other.0)
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for YololString {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Hash for YololString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
     }
 }
 
@@ -125,8 +143,7 @@ impl From<&str> for YololString {
 
 impl From<&YololInt> for YololString {
     fn from(int: &YololInt) -> Self {
-        let f: f64 = int.into();
-        YololString(format!("{}", f))
+        YololString(int.to_string())
     }
 }
 
@@ -138,28 +155,18 @@ impl Add for YololString {
     }
 }
 
+/// Remove the last occurrence of `rhs`, if any.
 impl Sub for YololString {
-    type Output = Option<YololString>;
+    type Output = YololString;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        if rhs.0.len() > self.0.len() {
-            return None;
-        } else if rhs.0 == self.0 {
-            return Some(Self("".into()));
-        } else {
-            for i in 0..(self.0.len() - rhs.0.len()) {
-                let s = self.0.len() - i - rhs.0.len();
-                let e = self.0.len() - i;
-                if rhs.0 == self.0[s..e] {
-                    return Some(Self(format!(
-                        "{}{}",
-                        &self.0[0..s],
-                        &self.0[e..self.0.len()]
-                    )));
-                }
+        match self.0.rfind(rhs.0.as_str()) {
+            Some(s) => {
+                let (head, tail) = self.0.split_at(s);
+                Self(format!("{}{}", head, &tail[rhs.0.len()..]))
             }
+            None => self,
         }
-        Some(self)
     }
 }
 
@@ -185,7 +192,7 @@ fn remove_test_1() {
     let b = " world";
     assert_eq!(
         YololString::from("Hello"),
-        (YololString::from(a) - YololString::from(b)).unwrap()
+        YololString::from(a) - YololString::from(b)
     )
 }
 
@@ -195,7 +202,7 @@ fn remove_test_2() {
     let b = " world";
     assert_eq!(
         YololString::from("Hello"),
-        (YololString::from(a) - YololString::from(b)).unwrap()
+        YololString::from(a) - YololString::from(b)
     )
 }
 
@@ -205,7 +212,7 @@ fn remove_test_3() {
     let a = " world";
     assert_eq!(
         YololString::from(" world"),
-        (YololString::from(a) - YololString::from(b)).unwrap()
+        YololString::from(a) - YololString::from(b)
     )
 }
 
@@ -215,6 +222,39 @@ fn remove_test_4() {
     let b = " world";
     assert_eq!(
         YololString::from("Hello world Hello"),
-        (YololString::from(a) - YololString::from(b)).unwrap()
+        YololString::from(a) - YololString::from(b)
     )
 }
+
+/// The last occurrence is removed, even when it starts the string.
+#[test]
+fn remove_prefix_test() {
+    let remove = |a: &str, b: &str| YololString::from(a) - YololString::from(b);
+    assert_eq!(remove("Hello", "He"), YololString::from("llo"));
+    assert_eq!(remove("abab", "ab"), YololString::from("ab"));
+    assert_eq!(remove("abc", "abc"), YololString::from(""));
+    assert_eq!(remove("abc", "x"), YololString::from("abc"));
+}
+
+#[test]
+fn non_ascii_test() {
+    let remove = |a: &str, b: &str| YololString::from(a) - YololString::from(b);
+    assert_eq!(remove("aé", "x"), YololString::from("aé"));
+    assert_eq!(remove("héhé", "é"), YololString::from("héh"));
+    assert_eq!(remove("é", "aé"), YololString::from("é"));
+    let mut a = YololString::from("aé");
+    assert_eq!(a.post_dec().unwrap(), YololString::from("aé").into());
+    assert_eq!(a, YololString::from("a"));
+    let mut a = YololString::from("é");
+    assert_eq!(a.pre_dec().unwrap(), YololString::from("").into());
+}
+
+#[test]
+fn decrement_empty_test() {
+    let mut a = YololString::from("");
+    assert_eq!(a.pre_dec().unwrap_err(), YololError::EmptyStringDecrement);
+    assert_eq!(
+        a.sqrt().unwrap_err(),
+        YololError::StringOperation { op: "sqrt" }
+    );
+}
@@ -0,0 +1,196 @@
+use std::io;
+use std::io::Write;
+
+use crate::devices::chip::CodeRunner;
+use crate::value::YololValue;
+use crate::Networks;
+
+/// Dumps network fields as a Value Change Dump, to be viewed in a waveform viewer like
+/// GTKWave. A tick lasts 200 ms, so it spans two VCD time units of 100 ms.
+#[derive(Debug)]
+pub struct Vcd<W: Write> {
+    writer: W,
+    signals: Vec<Signal>,
+    header: bool,
+}
+
+#[derive(Debug)]
+struct Signal {
+    network: String,
+    field: String,
+    id: String,
+    string: bool,
+    value: Option<YololValue>,
+}
+
+impl<W: Write> Vcd<W> {
+    /// Dump the fields `signals`, given as network and field name. The leading `:` of
+    /// field names is optional.
+    pub fn new(writer: W, signals: Vec<(String, String)>) -> Self {
+        let signals = signals
+            .into_iter()
+            .enumerate()
+            .map(|(i, (network, field))| Signal {
+                network,
+                field: field.trim_start_matches(':').to_lowercase(),
+                id: identifier(i),
+                string: false,
+                value: None,
+            })
+            .collect();
+        Self {
+            writer,
+            signals,
+            header: false,
+        }
+    }
+
+    /// Write the fields that changed since the last sample, at the current tick. Call it
+    /// after each `Networks::step`.
+    ///
+    /// Signals are declared on the first sample, as reals for numbers and as strings
+    /// otherwise. A signal that later changes type is converted.
+    pub fn sample<R: CodeRunner + Default>(&mut self, networks: &Networks<R>) -> io::Result<()> {
+        let values: Vec<Option<YololValue>> = self
+            .signals
+            .iter()
+            .map(|signal| {
+                networks
+                    .network(&signal.network)?
+                    .get_field(&signal.field)
+                    .cloned()
+            })
+            .collect();
+        if !self.header {
+            for (signal, value) in self.signals.iter_mut().zip(&values) {
+                signal.string = matches!(value, Some(YololValue::String(_)));
+            }
+            self.write_header()?;
+            self.header = true;
+        }
+        let mut time = Some(networks.tick());
+        for (signal, value) in self.signals.iter_mut().zip(values) {
+            let value = match value {
+                Some(value) => value,
+                None => continue,
+            };
+            if signal.value.as_ref() == Some(&value) {
+                continue;
+            }
+            if let Some(time) = time.take() {
+                writeln!(self.writer, "#{}", time * 2)?;
+            }
+            if signal.string {
+                writeln!(self.writer, "s{} {}", vcd_string(&value), signal.id)?;
+            } else {
+                writeln!(self.writer, "r{} {}", vcd_real(&value), signal.id)?;
+            }
+            signal.value = Some(value);
+        }
+        Ok(())
+    }
+
+    /// Give back the writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        writeln!(self.writer, "$version yolol-devices $end")?;
+        writeln!(self.writer, "$timescale 100 ms $end")?;
+        let mut networks: Vec<&str> = self.signals.iter().map(|s| s.network.as_str()).collect();
+        networks.sort_unstable();
+        networks.dedup();
+        for network in networks {
+            writeln!(self.writer, "$scope module {} $end", network)?;
+            for signal in self.signals.iter().filter(|s| s.network == network) {
+                let (kind, width) = if signal.string {
+                    ("string", 1)
+                } else {
+                    ("real", 64)
+                };
+                writeln!(
+                    self.writer,
+                    "$var {} {} {} {} $end",
+                    kind, width, signal.id, signal.field
+                )?;
+            }
+            writeln!(self.writer, "$upscope $end")?;
+        }
+        writeln!(self.writer, "$enddefinitions $end")
+    }
+}
+
+/// VCD identifiers are made of the printable ASCII characters.
+fn identifier(mut i: usize) -> String {
+    let mut id = String::new();
+    loop {
+        id.push((b'!' + (i % 94) as u8) as char);
+        i /= 94;
+        if i == 0 {
+            return id;
+        }
+        i -= 1;
+    }
+}
+
+/// Strings cannot hold whitespace in a VCD.
+fn vcd_string(value: &YololValue) -> String {
+    let s = match value {
+        YololValue::Int(v) => v.to_string(),
+        YololValue::String(v) => v.to_string(),
+    };
+    if s.is_empty() {
+        return "\"\"".to_string();
+    }
+    s.chars()
+        .map(|c| if c.is_whitespace() { '_' } else { c })
+        .collect()
+}
+
+/// Strings are dumped as 0.
+fn vcd_real(value: &YololValue) -> String {
+    match value {
+        YololValue::Int(v) => v.to_string(),
+        YololValue::String(_) => "0".to_string(),
+    }
+}
+
+#[test]
+fn identifier_test() {
+    assert_eq!(identifier(0), "!");
+    assert_eq!(identifier(93), "~");
+    assert_eq!(identifier(94), "!!");
+    assert_eq!(identifier(95), "\"!");
+}
+
+#[test]
+fn vcd_test() {
+    let mut networks = crate::increment_network();
+    let mut vcd = Vcd::new(
+        vec![],
+        vec![
+            ("network".to_string(), ":A".to_string()),
+            ("network".to_string(), "missing".to_string()),
+        ],
+    );
+    vcd.sample(&networks).unwrap();
+    networks.step();
+    vcd.sample(&networks).unwrap();
+    vcd.sample(&networks).unwrap();
+    let dump = String::from_utf8(vcd.into_inner()).unwrap();
+    assert_eq!(
+        dump,
+        "$version yolol-devices $end\n\
+         $timescale 100 ms $end\n\
+         $scope module network $end\n\
+         $var real 64 ! a $end\n\
+         $var real 64 \" missing $end\n\
+         $upscope $end\n\
+         $enddefinitions $end\n\
+         #0\n\
+         r0 !\n\
+         #2\n\
+         r1 !\n"
+    );
+}
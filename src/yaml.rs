@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+use std::ops::Index;
+
+use serde_yaml::Value;
+
+use crate::deserializer::Deserializer;
+
+/// A ship file read with serde_yaml. Scalars are kept as written, and the tag of a node,
+/// like `!rack`, is its type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum YamlNode {
+    Scalar(String),
+    Map(BTreeMap<String, YamlNode>, Option<String>),
+    Seq(Vec<YamlNode>, Option<String>),
+    None,
+}
+
+static NONE: YamlNode = YamlNode::None;
+
+impl YamlNode {
+    pub fn parse(source: &str) -> Result<Self, serde_yaml::Error> {
+        let value: Value = serde_yaml::from_str(source)?;
+        Ok(value.into())
+    }
+
+    fn with_tag(self, tag: String) -> Self {
+        match self {
+            YamlNode::Map(map, _) => YamlNode::Map(map, Some(tag)),
+            YamlNode::Seq(seq, _) => YamlNode::Seq(seq, Some(tag)),
+            node => node,
+        }
+    }
+}
+
+impl From<Value> for YamlNode {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => YamlNode::None,
+            Value::Bool(v) => YamlNode::Scalar(v.to_string()),
+            Value::Number(v) => YamlNode::Scalar(v.to_string()),
+            Value::String(v) => YamlNode::Scalar(v),
+            Value::Sequence(v) => YamlNode::Seq(v.into_iter().map(Into::into).collect(), None),
+            Value::Mapping(v) => YamlNode::Map(
+                v.into_iter()
+                    .filter_map(|(k, v)| match k.into() {
+                        YamlNode::Scalar(k) => Some((k, v.into())),
+                        _ => None,
+                    })
+                    .collect(),
+                None,
+            ),
+            Value::Tagged(v) => YamlNode::from(v.value).with_tag(v.tag.to_string()),
+        }
+    }
+}
+
+impl Index<String> for YamlNode {
+    type Output = YamlNode;
+
+    fn index(&self, index: String) -> &Self::Output {
+        self.get_field(&index).unwrap_or(&NONE)
+    }
+}
+
+impl Deserializer<YamlNode> for YamlNode {
+    fn get_field(&self, key: &str) -> Option<&YamlNode> {
+        match self {
+            YamlNode::Map(map, _) => map.get(key),
+            _ => None,
+        }
+    }
+
+    fn get_type(&self) -> Option<String> {
+        match self {
+            YamlNode::Map(_, tag) | YamlNode::Seq(_, tag) => tag.clone(),
+            _ => None,
+        }
+    }
+
+    fn as_map(&self) -> Option<BTreeMap<String, &YamlNode>> {
+        match self {
+            YamlNode::Map(map, _) => Some(map.iter().map(|(k, v)| (k.clone(), v)).collect()),
+            _ => None,
+        }
+    }
+
+    fn as_vec(&self) -> Option<Vec<&YamlNode>> {
+        match self {
+            YamlNode::Seq(seq, _) => Some(seq.iter().collect()),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            YamlNode::Scalar(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn ship_test() {
+    use crate::devices::chip::NoneRunner;
+    use crate::Networks;
+
+    let ship = YamlNode::parse(
+        "networks:
+  - name: main
+    devices:
+      - !lamp
+        LampOn: {name: Light, value: 1}
+        LampLumens: Lumens
+      - !rack
+        module: !chip_reader
+          slot1: !yolol_chip
+            script: door.yolol
+",
+    )
+    .unwrap();
+    let networks = Networks::<NoneRunner>::deserialize(&ship).unwrap();
+    let main = networks.network("main").unwrap();
+    assert_eq!(main.devices().len(), 2);
+    assert_eq!(main.get_field("light"), Some(&1.into()));
+}
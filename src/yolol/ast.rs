@@ -0,0 +1,36 @@
+use crate::value::BinaryOp;
+use crate::value::IncDecOp;
+use crate::value::UnaryOp;
+use crate::value::YololValue;
+
+/// A variable, local to the chip or a `:global` of its network. Names are lowercase and
+/// globals are stored without their `:`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Variable {
+    Local(String),
+    Global(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Value(YololValue),
+    Variable(Variable),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    IncDec(IncDecOp, Variable),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    /// `a = b`, or a compound assignment like `a += b`.
+    Assign(Variable, Option<BinaryOp>, Expr),
+    IncDec(IncDecOp, Variable),
+    If(Expr, Vec<Statement>, Vec<Statement>),
+    Goto(Expr),
+}
+
+/// A line of YOLOL, its statements running in order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Line {
+    pub statements: Vec<Statement>,
+}
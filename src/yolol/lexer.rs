@@ -0,0 +1,122 @@
+use super::parser::ParseError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Token {
+    /// A number or string literal, as written.
+    Literal(String),
+    /// A local name or a keyword, lowercased.
+    Ident(String),
+    /// A global name, lowercased and without its `:`.
+    Global(String),
+    Symbol(&'static str),
+}
+
+/// Longest first, so that `+=` is not read as `+` and `=`.
+const SYMBOLS: &[&str] = &[
+    "++", "--", "+=", "-=", "*=", "/=", "%=", "^=", "==", "!=", "<=", ">=", "=", "<", ">", "+",
+    "-", "*", "/", "%", "^", "!", "(", ")",
+];
+
+/// Split a line into tokens, each with its 1-based column. Comments are dropped.
+pub(super) fn tokenize(line: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            break;
+        } else if c == '"' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            if i >= chars.len() {
+                return Err(ParseError::new(column, "unterminated string"));
+            }
+            i += 1;
+            tokens.push((column, Token::Literal(chars[start..i].iter().collect())));
+        } else if c.is_ascii_digit() || (c == '.' && next_is_digit(&chars, i)) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push((column, Token::Literal(chars[start..i].iter().collect())));
+        } else if c == ':' {
+            i += 1;
+            let name = read_name(&chars, &mut i);
+            if name.is_empty() {
+                return Err(ParseError::new(column, "expected a name after `:`"));
+            }
+            tokens.push((column, Token::Global(name)));
+        } else if c.is_alphabetic() || c == '_' {
+            tokens.push((column, Token::Ident(read_name(&chars, &mut i))));
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            match SYMBOLS.iter().find(|s| rest.starts_with(*s)) {
+                Some(symbol) => {
+                    i += symbol.len();
+                    tokens.push((column, Token::Symbol(symbol)));
+                }
+                None => return Err(ParseError::new(column, format!("unexpected {:?}", c))),
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Find the globals a line mentions, lowercased, each with the 1-based column of its `:`.
+pub fn globals(line: &str) -> Result<Vec<(usize, String)>, ParseError> {
+    Ok(tokenize(line)?
+        .into_iter()
+        .filter_map(|(column, token)| match token {
+            Token::Global(name) => Some((column, name)),
+            _ => None,
+        })
+        .collect())
+}
+
+fn next_is_digit(chars: &[char], i: usize) -> bool {
+    chars.get(i + 1).is_some_and(|c| c.is_ascii_digit())
+}
+
+fn read_name(chars: &[char], i: &mut usize) -> String {
+    let start = *i;
+    while *i < chars.len() && (chars[*i].is_alphanumeric() || chars[*i] == '_') {
+        *i += 1;
+    }
+    chars[start..*i].iter().collect::<String>().to_lowercase()
+}
+
+#[test]
+fn tokenize_test() {
+    let tokens: Vec<Token> = tokenize(":Door+=1.5 // open")
+        .unwrap()
+        .into_iter()
+        .map(|(_, token)| token)
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Global("door".to_string()),
+            Token::Symbol("+="),
+            Token::Literal("1.5".to_string()),
+        ]
+    );
+    assert_eq!(tokenize("a=\"b").unwrap_err().column, 3);
+}
+
+#[test]
+fn globals_test() {
+    assert_eq!(
+        globals("a = :Door + :x // :y").unwrap(),
+        vec![(5, "door".to_string()), (13, "x".to_string())]
+    );
+}
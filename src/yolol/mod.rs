@@ -0,0 +1,17 @@
+//! A YOLOL interpreter, providing a `CodeRunner` for the chips.
+
+mod ast;
+mod lexer;
+mod parser;
+mod runner;
+
+pub use ast::Expr;
+pub use ast::Line;
+pub use ast::Statement;
+pub use ast::Variable;
+pub use lexer::globals;
+pub use parser::parse_expr;
+pub use parser::parse_line;
+pub use parser::ParseError;
+pub use runner::Environment;
+pub use runner::YololRunner;
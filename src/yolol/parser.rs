@@ -0,0 +1,382 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use super::ast::Expr;
+use super::ast::Line;
+use super::ast::Statement;
+use super::ast::Variable;
+use super::lexer::tokenize;
+use super::lexer::Token;
+use crate::value::BinaryOp;
+use crate::value::IncDecOp;
+use crate::value::UnaryOp;
+use crate::value::YololValue;
+
+/// A syntax error, at a 1-based column of the line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub(super) fn new(column: usize, message: impl Into<String>) -> Self {
+        Self {
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+const KEYWORDS: &[&str] = &[
+    "if", "then", "else", "end", "goto", "and", "or", "not", "abs", "sqrt", "sin", "cos", "tan",
+    "asin", "acos", "atan",
+];
+
+/// Parse a line of YOLOL.
+pub fn parse_line(line: &str) -> Result<Line, ParseError> {
+    let mut parser = Parser::new(line)?;
+    let statements = parser.statements()?;
+    match parser.peek() {
+        None => Ok(Line { statements }),
+        Some(_) => Err(parser.unexpected()),
+    }
+}
+
+/// Parse a single YOLOL expression, like `:Door == 1 and a > 2`.
+pub fn parse_expr(expr: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser::new(expr)?;
+    let expr = parser.expr()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(_) => Err(parser.unexpected()),
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn new(line: &str) -> Result<Self, ParseError> {
+        Ok(Self {
+            tokens: tokenize(line)?,
+            pos: 0,
+            end: line.chars().count() + 1,
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.end, |(column, _)| *column)
+    }
+
+    fn unexpected(&self) -> ParseError {
+        match self.peek() {
+            Some(Token::Literal(s)) | Some(Token::Ident(s)) => {
+                ParseError::new(self.column(), format!("unexpected `{}`", s))
+            }
+            Some(Token::Global(s)) => {
+                ParseError::new(self.column(), format!("unexpected `:{}`", s))
+            }
+            Some(Token::Symbol(s)) => ParseError::new(self.column(), format!("unexpected `{}`", s)),
+            None => ParseError::new(self.column(), "unexpected end of line"),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(s)) if s == keyword)
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol)
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        if self.is_keyword(keyword) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(ParseError::new(
+                self.column(),
+                format!("expected `{}`", keyword),
+            ))
+        }
+    }
+
+    /// Statements until the end of the line or a keyword closing a block.
+    fn statements(&mut self) -> Result<Vec<Statement>, ParseError> {
+        let mut statements = vec![];
+        while self.peek().is_some() && !self.is_keyword("else") && !self.is_keyword("end") {
+            statements.push(self.statement()?);
+        }
+        Ok(statements)
+    }
+
+    fn statement(&mut self) -> Result<Statement, ParseError> {
+        if self.is_keyword("if") {
+            self.pos += 1;
+            let condition = self.expr()?;
+            self.expect_keyword("then")?;
+            let then = self.statements()?;
+            let otherwise = if self.is_keyword("else") {
+                self.pos += 1;
+                self.statements()?
+            } else {
+                vec![]
+            };
+            self.expect_keyword("end")?;
+            return Ok(Statement::If(condition, then, otherwise));
+        }
+        if self.is_keyword("goto") {
+            self.pos += 1;
+            return Ok(Statement::Goto(self.expr()?));
+        }
+        if self.is_symbol("++") || self.is_symbol("--") {
+            let op = if self.is_symbol("++") {
+                IncDecOp::PreInc
+            } else {
+                IncDecOp::PreDec
+            };
+            self.pos += 1;
+            return Ok(Statement::IncDec(op, self.variable()?));
+        }
+        let variable = self.variable()?;
+        match self.next() {
+            Some(Token::Symbol("=")) => Ok(Statement::Assign(variable, None, self.expr()?)),
+            Some(Token::Symbol("++")) => Ok(Statement::IncDec(IncDecOp::PostInc, variable)),
+            Some(Token::Symbol("--")) => Ok(Statement::IncDec(IncDecOp::PostDec, variable)),
+            Some(Token::Symbol(symbol)) if BinaryOp::from_assign_symbol(symbol).is_some() => {
+                let op = BinaryOp::from_assign_symbol(symbol);
+                Ok(Statement::Assign(variable, op, self.expr()?))
+            }
+            _ => {
+                self.pos -= 1;
+                Err(ParseError::new(self.column(), "expected an assignment"))
+            }
+        }
+    }
+
+    fn variable(&mut self) -> Result<Variable, ParseError> {
+        match self.peek() {
+            Some(Token::Global(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(Variable::Global(name))
+            }
+            Some(Token::Ident(name)) if !KEYWORDS.contains(&name.as_str()) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(Variable::Local(name))
+            }
+            _ => Err(ParseError::new(self.column(), "expected a variable")),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        self.binary(0)
+    }
+
+    /// Binary operators, from the loosest to the tightest binding. `not` sits between
+    /// `and` and the comparisons.
+    fn binary(&mut self, level: usize) -> Result<Expr, ParseError> {
+        const LEVELS: &[&[BinaryOp]] = &[
+            &[BinaryOp::Or],
+            &[BinaryOp::And],
+            &[
+                BinaryOp::Eq,
+                BinaryOp::Ne,
+                BinaryOp::Lt,
+                BinaryOp::Le,
+                BinaryOp::Gt,
+                BinaryOp::Ge,
+            ],
+            &[BinaryOp::Add, BinaryOp::Sub],
+            &[BinaryOp::Mul, BinaryOp::Div, BinaryOp::Mod],
+        ];
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+        if level == 2 && self.is_keyword("not") {
+            self.pos += 1;
+            let operand = self.binary(level)?;
+            return Ok(Expr::Unary(UnaryOp::Not, Box::new(operand)));
+        }
+        let mut lhs = self.binary(level + 1)?;
+        while let Some(op) = self.binary_op().filter(|op| LEVELS[level].contains(op)) {
+            self.pos += 1;
+            let rhs = self.binary(level + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn binary_op(&self) -> Option<BinaryOp> {
+        match self.peek()? {
+            Token::Symbol(s) => BinaryOp::from_symbol(s),
+            Token::Ident(s) if s == "and" || s == "or" => BinaryOp::from_symbol(s),
+            _ => None,
+        }
+    }
+
+    /// Negation and the keyword operators, like `sqrt a`.
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        let op = match self.peek() {
+            Some(Token::Symbol("-")) => Some(UnaryOp::Neg),
+            Some(Token::Ident(s)) if s != "not" => UnaryOp::from_symbol(s),
+            _ => None,
+        };
+        match op {
+            Some(op) => {
+                self.pos += 1;
+                let operand = self.unary()?;
+                Ok(Expr::Unary(op, Box::new(operand)))
+            }
+            None => self.pow(),
+        }
+    }
+
+    /// `^` is left associative in YOLOL.
+    fn pow(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.factorial()?;
+        while self.is_symbol("^") {
+            self.pos += 1;
+            let rhs = if self.is_symbol("-") {
+                self.unary()?
+            } else {
+                self.factorial()?
+            };
+            lhs = Expr::Binary(BinaryOp::Pow, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn factorial(&mut self) -> Result<Expr, ParseError> {
+        let mut operand = self.primary()?;
+        while self.is_symbol("!") && !matches!(self.peek_at(1), Some(Token::Symbol("="))) {
+            self.pos += 1;
+            operand = Expr::Unary(UnaryOp::Fac, Box::new(operand));
+        }
+        Ok(operand)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        let column = self.column();
+        match self.peek().cloned() {
+            Some(Token::Literal(literal)) => {
+                self.pos += 1;
+                YololValue::parse_literal(&literal)
+                    .map(Expr::Value)
+                    .map_err(|e| ParseError::new(column, e.to_string()))
+            }
+            Some(Token::Symbol("(")) => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                if !self.is_symbol(")") {
+                    return Err(ParseError::new(self.column(), "expected `)`"));
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            Some(Token::Symbol("++")) | Some(Token::Symbol("--")) => {
+                let op = if self.is_symbol("++") {
+                    IncDecOp::PreInc
+                } else {
+                    IncDecOp::PreDec
+                };
+                self.pos += 1;
+                Ok(Expr::IncDec(op, self.variable()?))
+            }
+            Some(Token::Global(_)) | Some(Token::Ident(_)) => {
+                let variable = self.variable()?;
+                if self.is_symbol("++") {
+                    self.pos += 1;
+                    Ok(Expr::IncDec(IncDecOp::PostInc, variable))
+                } else if self.is_symbol("--") {
+                    self.pos += 1;
+                    Ok(Expr::IncDec(IncDecOp::PostDec, variable))
+                } else {
+                    Ok(Expr::Variable(variable))
+                }
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+}
+
+#[test]
+fn precedence_test() {
+    let expr = parse_expr("1 + 2 * 3 == 7 and not a").unwrap();
+    let seven = Expr::Binary(
+        BinaryOp::Eq,
+        Box::new(Expr::Binary(
+            BinaryOp::Add,
+            Box::new(Expr::Value(1.into())),
+            Box::new(Expr::Binary(
+                BinaryOp::Mul,
+                Box::new(Expr::Value(2.into())),
+                Box::new(Expr::Value(3.into())),
+            )),
+        )),
+        Box::new(Expr::Value(7.into())),
+    );
+    let not_a = Expr::Unary(
+        UnaryOp::Not,
+        Box::new(Expr::Variable(Variable::Local("a".to_string()))),
+    );
+    assert_eq!(
+        expr,
+        Expr::Binary(BinaryOp::And, Box::new(seven), Box::new(not_a))
+    );
+}
+
+#[test]
+fn parse_line_test() {
+    let line = parse_line("if :Door then a++ b-=2 else goto 3 end c=\"x\"").unwrap();
+    assert_eq!(line.statements.len(), 2);
+    assert!(
+        matches!(&line.statements[0], Statement::If(_, then, otherwise)
+        if then.len() == 2 && otherwise.len() == 1)
+    );
+    assert_eq!(
+        line.statements[1],
+        Statement::Assign(
+            Variable::Local("c".to_string()),
+            None,
+            Expr::Value("x".into())
+        )
+    );
+    assert!(parse_line("").unwrap().statements.is_empty());
+}
+
+#[test]
+fn parse_error_test() {
+    assert_eq!(parse_line("a = ").unwrap_err().column, 5);
+    assert_eq!(parse_line("if a then b=1").unwrap_err().column, 14);
+    assert_eq!(parse_line("a = 1 )").unwrap_err().column, 7);
+}
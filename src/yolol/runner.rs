@@ -0,0 +1,281 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+use super::ast::Expr;
+use super::ast::Line;
+use super::ast::Statement;
+use super::ast::Variable;
+use super::parser::parse_line;
+use crate::devices::chip::CodeRunner;
+use crate::devices::chip::RunnerState;
+use crate::devices::chip::RuntimeError;
+use crate::devices::chip::StepOutcome;
+use crate::devices::chip::CHIP_MAX_LINES;
+use crate::field::Field;
+use crate::value::YololValue;
+
+/// The variables a chip sees. Undefined variables read as 0.
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    pub locals: BTreeMap<String, YololValue>,
+    pub globals: BTreeMap<String, YololValue>,
+    written: BTreeSet<String>,
+}
+
+impl Environment {
+    pub fn get(&self, variable: &Variable) -> YololValue {
+        match variable {
+            Variable::Local(name) => self.locals.get(name),
+            Variable::Global(name) => self.globals.get(name),
+        }
+        .cloned()
+        .unwrap_or_default()
+    }
+
+    pub fn set(&mut self, variable: &Variable, value: YololValue) {
+        match variable {
+            Variable::Local(name) => {
+                self.locals.insert(name.clone(), value);
+            }
+            Variable::Global(name) => {
+                self.written.insert(name.clone());
+                self.globals.insert(name.clone(), value);
+            }
+        }
+    }
+
+    /// Get the names of the globals written since the last `clear_written`.
+    pub fn written(&self) -> impl Iterator<Item = &String> {
+        self.written.iter()
+    }
+
+    pub fn clear_written(&mut self) {
+        self.written.clear();
+    }
+
+    pub fn eval(&mut self, expr: &Expr) -> Result<YololValue, RuntimeError> {
+        match expr {
+            Expr::Value(value) => Ok(value.clone()),
+            Expr::Variable(variable) => Ok(self.get(variable)),
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = self.eval(lhs)?;
+                let rhs = self.eval(rhs)?;
+                Ok(lhs.apply_binary(*op, &rhs)?)
+            }
+            Expr::Unary(op, operand) => Ok(self.eval(operand)?.apply_unary(*op)?),
+            Expr::IncDec(op, variable) => {
+                let mut value = self.get(variable);
+                let result = value.apply_inc_dec(*op)?;
+                self.set(variable, value);
+                Ok(result)
+            }
+        }
+    }
+
+    /// Run the statements of a line, returning the target of the `goto` that ended it.
+    pub fn exec(&mut self, statements: &[Statement]) -> Result<Option<usize>, RuntimeError> {
+        for statement in statements {
+            match statement {
+                Statement::Assign(variable, op, expr) => {
+                    let rhs = self.eval(expr)?;
+                    let value = match op {
+                        Some(op) => self.get(variable).apply_binary(*op, &rhs)?,
+                        None => rhs,
+                    };
+                    self.set(variable, value);
+                }
+                Statement::IncDec(op, variable) => {
+                    self.eval(&Expr::IncDec(*op, variable.clone()))?;
+                }
+                Statement::If(condition, then, otherwise) => {
+                    let condition = match self.eval(condition)? {
+                        YololValue::Int(v) => bool::from(&v),
+                        YololValue::String(_) => return Err(RuntimeError::TypeMismatch),
+                    };
+                    let branch = if condition { then } else { otherwise };
+                    if let Some(line) = self.exec(branch)? {
+                        return Ok(Some(line));
+                    }
+                }
+                Statement::Goto(expr) => match self.eval(expr)? {
+                    YololValue::Int(v) => {
+                        let line = i64::from(&v).clamp(1, CHIP_MAX_LINES as i64);
+                        return Ok(Some(line as usize));
+                    }
+                    YololValue::String(_) => return Err(RuntimeError::TypeMismatch),
+                },
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Runs YOLOL scripts. A chip holds `CHIP_MAX_LINES` lines, blank lines taking a tick like
+/// the others. A line that does not parse does nothing.
+#[derive(Debug, Default)]
+pub struct YololRunner {
+    lines: Vec<Option<Line>>,
+    line: usize,
+    env: Environment,
+    /// The syntax errors of the script, by 1-based line.
+    errors: Vec<(usize, super::ParseError)>,
+}
+
+impl YololRunner {
+    /// Load a script, returning the syntax errors by 1-based line.
+    pub fn load_source(&mut self, source: &str) -> Vec<(usize, super::ParseError)> {
+        let mut errors = vec![];
+        self.lines = source
+            .lines()
+            .enumerate()
+            .map(|(i, line)| match parse_line(line) {
+                Ok(line) => Some(line),
+                Err(e) => {
+                    errors.push((i + 1, e));
+                    None
+                }
+            })
+            .collect();
+        if self.lines.len() < CHIP_MAX_LINES {
+            self.lines.resize(CHIP_MAX_LINES, Some(Line::default()));
+        }
+        self.line = 0;
+        errors
+    }
+
+    /// Get the 1-based line to run next.
+    pub fn line(&self) -> usize {
+        self.line + 1
+    }
+
+    pub fn env(&self) -> &Environment {
+        &self.env
+    }
+
+    pub fn env_mut(&mut self) -> &mut Environment {
+        &mut self.env
+    }
+}
+
+impl CodeRunner for YololRunner {
+    fn parse(&mut self, source: &str) -> Option<()> {
+        self.errors = self.load_source(source);
+        Some(())
+    }
+
+    fn parse_errors(&self) -> Vec<(usize, String)> {
+        self.errors
+            .iter()
+            .map(|(line, error)| (*line, error.to_string()))
+            .collect()
+    }
+
+    fn step(&mut self) -> StepOutcome {
+        self.env.clear_written();
+        let line = self.line;
+        let mut outcome = StepOutcome {
+            line: line + 1,
+            error: None,
+        };
+        let result = match self.lines.get(line) {
+            Some(Some(l)) => self.env.exec(&l.statements),
+            _ => Ok(None),
+        };
+        self.line = match result {
+            Ok(Some(target)) => target - 1,
+            Ok(None) => line + 1,
+            Err(error) => {
+                outcome.error = Some(error);
+                line + 1
+            }
+        };
+        if self.line >= self.lines.len() {
+            self.line = 0;
+        }
+        outcome
+    }
+
+    fn update_globals(&mut self, globals: Vec<Field>) {
+        for global in globals {
+            self.env
+                .globals
+                .insert(global.name().to_string(), (*global).clone());
+        }
+    }
+
+    fn get_global(&self) -> Vec<Field> {
+        self.env
+            .written()
+            .map(|name| {
+                let mut field = Field::default();
+                field.set_name(name.clone());
+                *field = self.env.globals[name].clone();
+                field
+            })
+            .collect()
+    }
+
+    fn clear_written(&mut self) {
+        self.env.clear_written();
+    }
+
+    fn save_state(&self) -> RunnerState {
+        RunnerState {
+            line: self.line + 1,
+            locals: self.env.locals.clone(),
+        }
+    }
+
+    fn load_state(&mut self, state: &RunnerState) {
+        self.line = state.line.saturating_sub(1);
+        self.env.locals = state.locals.clone();
+    }
+}
+
+#[cfg(test)]
+fn run(source: &str, ticks: usize) -> YololRunner {
+    let mut runner = YololRunner::default();
+    assert!(runner.load_source(source).is_empty());
+    for _ in 0..ticks {
+        runner.step();
+    }
+    runner
+}
+
+#[test]
+fn step_test() {
+    let runner = run("a = 1\nb = a + 1 :out = b * 2\ngoto 2", 4);
+    assert_eq!(runner.env().locals["b"], 2.into());
+    assert_eq!(runner.env().globals["out"], 4.into());
+    assert_eq!(runner.line(), 3);
+    assert_eq!(runner.get_global().len(), 1);
+}
+
+#[test]
+fn wrap_test() {
+    let runner = run("i++", CHIP_MAX_LINES + 1);
+    assert_eq!(runner.env().locals["i"], 2.into());
+}
+
+#[test]
+fn runtime_error_test() {
+    let mut runner = YololRunner::default();
+    runner.load_source("a = 1 / 0 b = 1\nc = 1");
+    let outcome = runner.step();
+    assert_eq!(
+        outcome.error,
+        Some(RuntimeError::Value(crate::value::YololError::DivideByZero))
+    );
+    assert!(!runner.env().locals.contains_key("b"));
+    assert_eq!(runner.line(), 2);
+}
+
+#[test]
+fn if_test() {
+    let runner = run(
+        "if 1 > 2 then a = 1 else a = 2 if a == 2 then goto 5 end a = 3 end",
+        1,
+    );
+    assert_eq!(runner.env().locals["a"], 2.into());
+    assert_eq!(runner.line(), 5);
+}